@@ -0,0 +1,110 @@
+use crate::extend::push_witness;
+use crate::formula::ClauseRef;
+use crate::preprocessor::SATContext;
+use crate::proof::delete_clause;
+
+// Expects the clause to be marked. Returns whether every resolvent on 'lit'
+// is a tautology, or 'None' once the check budget is exhausted.
+fn blocked_on(ctx: &mut SATContext, lit: i32, budget: &mut usize) -> Option<bool> {
+    for &d_id in &ctx.formula.matrix[-lit] {
+        if ctx.formula.clauses.is_garbage(d_id) {
+            continue;
+        }
+        if *budget == 0 {
+            return None;
+        }
+        *budget -= 1;
+        ctx.stats.block_checks += 1;
+        let tautological = ctx.formula.clauses[d_id]
+            .iter()
+            .any(|&other| other != -lit && ctx.formula.marks.is_marked(-other));
+        if !tautological {
+            return Some(false);
+        }
+    }
+    Some(true)
+}
+
+// Returns a literal on which the clause is blocked, 0 if there is none and
+// 'None' if the budget ran out.
+fn blocking_literal(ctx: &mut SATContext, clause_id: ClauseRef, budget: &mut usize) -> Option<i32> {
+    let literals = ctx.formula.clauses[clause_id].to_vec();
+    for &lit in &literals {
+        ctx.formula.marks.mark(lit);
+    }
+    let mut result = Some(0);
+    for &lit in &literals {
+        // the negations of frozen literals may be added later
+        if ctx.formula.is_frozen(lit.unsigned_abs() as usize) {
+            continue;
+        }
+        match blocked_on(ctx, lit, budget) {
+            Some(true) => {
+                result = Some(lit);
+                break;
+            }
+            Some(false) => {}
+            None => {
+                result = None;
+                break;
+            }
+        }
+    }
+    for &lit in &literals {
+        ctx.formula.marks.unmark(lit);
+    }
+    result
+}
+
+/// Blocked clause elimination: removes clauses with a literal whose
+/// resolvents with all clauses containing its negation are tautological.
+/// Sweeps are repeated while clauses are removed, but the number of
+/// resolvents checked is bounded by [`Config::block_limit`].
+///
+/// [`Config::block_limit`]: crate::Config::block_limit
+pub(crate) fn eliminate_blocked(ctx: &mut SATContext) {
+    verbose!(
+        ctx.config.verbosity,
+        1,
+        "starting blocked clause elimination"
+    );
+    ctx.formula.connect_all_clauses(ctx.config.verbosity);
+    let mut budget = ctx.config.block_limit;
+    let before = ctx.stats.blocked;
+    let mut removed = true;
+    'sweeps: while removed {
+        removed = false;
+        for clause_id in ctx.formula.clauses.refs().to_vec() {
+            if ctx.formula.clauses.is_garbage(clause_id) {
+                continue;
+            }
+            match blocking_literal(ctx, clause_id, &mut budget) {
+                None => {
+                    verbose!(ctx.config.verbosity, 2, "blocked clause budget exhausted");
+                    break 'sweeps;
+                }
+                Some(0) => {}
+                Some(lit) => {
+                    LOG!(
+                        ctx.config.verbosity,
+                        "clause {:?} blocked on {}",
+                        &ctx.formula.clauses[clause_id],
+                        lit
+                    );
+                    let clause = ctx.formula.clauses[clause_id].to_vec();
+                    push_witness(ctx, lit, clause);
+                    delete_clause(ctx, clause_id);
+                    ctx.stats.blocked += 1;
+                    removed = true;
+                }
+            }
+        }
+    }
+    verbose!(
+        ctx.config.verbosity,
+        1,
+        "eliminated {} blocked clauses",
+        ctx.stats.blocked - before
+    );
+    ctx.formula.collect_garbage_clauses(ctx.config.verbosity);
+}
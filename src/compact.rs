@@ -0,0 +1,163 @@
+use crate::error::{BabysubError, Result};
+use crate::formula::CNFFormula;
+use crate::parse::open_input;
+use crate::print::open_output;
+use std::io::{BufRead, BufReader, Write};
+
+/// Dense renumbering of the variables still occurring in a simplified
+/// formula, which maps the variables of the compacted output back to the
+/// original ones.
+#[derive(Debug, Clone, Default)]
+pub struct Mapping {
+    // Number of variables of the original formula.
+    variables: usize,
+    // Original variable of each compacted variable, indexed from zero.
+    original: Vec<usize>,
+    // Compacted variable of each original one, zero if it was dropped.
+    compacted: Vec<i32>,
+}
+
+impl Mapping {
+    fn from_original(variables: usize, original: Vec<usize>) -> Self {
+        let mut compacted = vec![0; variables + 1];
+        for (index, &var) in original.iter().enumerate() {
+            compacted[var] = index as i32 + 1;
+        }
+        Mapping {
+            variables,
+            original,
+            compacted,
+        }
+    }
+
+    // Keeps the variables occurring in some clause and the frozen ones, in
+    // their original order.
+    pub(crate) fn new(formula: &CNFFormula) -> Self {
+        let mut occurs = vec![false; formula.variables + 1];
+        for clause in formula.clauses() {
+            for &lit in clause {
+                occurs[lit.unsigned_abs() as usize] = true;
+            }
+        }
+        let original = (1..=formula.variables)
+            .filter(|&var| occurs[var] || formula.is_frozen(var))
+            .collect();
+        Mapping::from_original(formula.variables, original)
+    }
+
+    /// Reads a mapping file written with
+    /// [`Preprocessor::write_mapping`](crate::Preprocessor::write_mapping).
+    pub fn read(path: &str, verbosity: i32) -> Result<Self> {
+        let (input, compression) = open_input(path, verbosity)?;
+        let mut original = Vec::new();
+        let mut variables = None;
+        for (index, line) in BufReader::new(input).lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.map_err(|e| BabysubError::stream(compression, e))?;
+            if line.starts_with('c') || line.trim().is_empty() {
+                continue;
+            }
+            let parse_error = |column: usize, message: &str| BabysubError::Parse {
+                path: path.to_string(),
+                line: line_number,
+                column,
+                message: message.to_string(),
+            };
+            if let Some(header) = line.strip_prefix("p mapping") {
+                let count = header
+                    .split_whitespace()
+                    .nth(1)
+                    .and_then(|v| v.parse().ok());
+                variables = Some(count.ok_or_else(|| parse_error(1, "Invalid mapping header."))?);
+                continue;
+            }
+            let variables = variables.ok_or_else(|| parse_error(1, "Mapping header not found."))?;
+            let mut tokens = line.split_whitespace();
+            let new: Option<usize> = tokens.next().and_then(|token| token.parse().ok());
+            let old: Option<usize> = tokens.next().and_then(|token| token.parse().ok());
+            match (new, old) {
+                (Some(new), Some(old))
+                    if new == original.len() + 1 && (1..=variables).contains(&old) =>
+                {
+                    original.push(old)
+                }
+                _ => {
+                    return Err(parse_error(
+                        1,
+                        "Expected the next compacted and its original variable.",
+                    ))
+                }
+            }
+        }
+        Ok(Mapping::from_original(variables.unwrap_or(0), original))
+    }
+
+    /// Number of variables of the original formula.
+    pub fn variables(&self) -> usize {
+        self.variables
+    }
+
+    /// Number of variables of the compacted formula.
+    pub fn len(&self) -> usize {
+        self.original.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.original.is_empty()
+    }
+
+    /// Original variable of a variable of the compacted formula.
+    pub fn original(&self, var: usize) -> Option<usize> {
+        var.checked_sub(1)
+            .and_then(|index| self.original.get(index))
+            .copied()
+    }
+
+    // Literal of the compacted formula for an original literal which has to
+    // be kept by the mapping.
+    pub(crate) fn compact(&self, lit: i32) -> i32 {
+        let var = self.compacted[lit.unsigned_abs() as usize];
+        debug_assert!(var != 0, "literal {} dropped by compaction", lit);
+        if lit < 0 {
+            -var
+        } else {
+            var
+        }
+    }
+
+    /// Translates a model of the compacted formula, given as DIMACS
+    /// literals, back to the original variables. Literals beyond the
+    /// compacted variables are ignored.
+    pub fn translate(&self, model: &[i32]) -> Vec<i32> {
+        model
+            .iter()
+            .filter_map(|&lit| {
+                let var = self.original(lit.unsigned_abs() as usize)? as i32;
+                Some(if lit < 0 { -var } else { var })
+            })
+            .collect()
+    }
+
+    pub(crate) fn write(&self, path: &str, level: Option<u32>, verbosity: i32) -> Result<()> {
+        verbose!(
+            verbosity,
+            1,
+            "writing mapping of {} variables to '{}'",
+            self.len(),
+            path
+        );
+        let (mut output, compression) = open_output(path, level)?;
+        self.write_to(&mut output)
+            .and_then(|()| output.finish())
+            .map_err(|e| BabysubError::stream(compression, e))
+    }
+
+    fn write_to(&self, output: &mut dyn Write) -> std::io::Result<()> {
+        writeln!(output, "c compacted variable followed by the original one")?;
+        writeln!(output, "p mapping {} {}", self.len(), self.variables)?;
+        for (index, var) in self.original.iter().enumerate() {
+            writeln!(output, "{} {}", index + 1, var)?;
+        }
+        output.flush()
+    }
+}
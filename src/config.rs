@@ -0,0 +1,106 @@
+/// Options controlling a [`Preprocessor`](crate::Preprocessor) run.
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Path of the DIMACS input, `"<stdin>"` to read standard input.
+    pub input_path: String,
+    /// Path of the DIMACS output, `"<stdout>"` to write standard output.
+    pub output_path: String,
+    /// Parse uncompressed input files from a memory mapping instead of
+    /// reading them through a buffer. Disabled by default, as the file must
+    /// not be modified while it is parsed.
+    pub mmap: bool,
+    /// Reject input which is not exactly DIMACS, like clause counts not
+    /// matching the header or clauses without terminating zero.
+    pub strict: bool,
+    /// Grow the variable range for literals beyond the header instead of
+    /// rejecting them.
+    pub relaxed: bool,
+    /// Negative values suppress all messages, higher values print more.
+    pub verbosity: i32,
+    /// Use backward instead of forward subsumption.
+    pub backward_mode: bool,
+    /// Split backward subsumption over this many threads. The result is
+    /// the same for any number, and strengthening always runs on one.
+    pub threads: usize,
+    /// Repeat forward and backward subsumption of strengthened clauses until
+    /// a fixpoint, which takes precedence over `backward_mode`.
+    pub combined_mode: bool,
+    /// Stop combined subsumption after this many rounds.
+    pub subsume_rounds: usize,
+    /// Propagate unit clauses at the root level before subsumption.
+    pub propagate: bool,
+    /// Learn units by failed literal probing, which implies propagation.
+    pub probe: bool,
+    /// Stop probing after visiting this many clauses per literal
+    /// occurrence in the formula.
+    pub probe_effort: usize,
+    /// Replace equivalent literals found in binary clauses.
+    pub substitute: bool,
+    /// Strengthen clauses by self-subsuming resolution while subsuming.
+    pub strengthen: bool,
+    /// Remove clauses containing pure literals after subsumption.
+    pub pure: bool,
+    /// Remove blocked clauses after subsumption.
+    pub block: bool,
+    /// Stop blocked clause elimination after checking this many resolvents.
+    pub block_limit: usize,
+    /// Run bounded variable elimination after subsumption.
+    pub eliminate: bool,
+    /// Skip variables with more occurrences of either sign.
+    pub elim_occ_limit: usize,
+    /// Do not eliminate variables producing larger resolvents.
+    pub elim_clause_limit: usize,
+    /// Variables which are never eliminated or substituted.
+    pub frozen: Vec<usize>,
+    /// Write a DRAT proof of all clause additions and deletions here.
+    pub proof_path: Option<String>,
+    /// Use the binary instead of the textual proof encoding.
+    pub binary_proof: bool,
+    /// Write an LRAT proof with antecedent clause ids instead of DRAT.
+    pub lrat: bool,
+    /// Renumber the variables of the output densely, see
+    /// [`Mapping`](crate::Mapping).
+    pub compact: bool,
+    /// Level of compressed outputs, proofs and reconstruction files, with
+    /// the default of each format if unset. Rejected for lz4 outputs.
+    pub compression_level: Option<u32>,
+    /// Add a hash signature of the simplified formula to the output.
+    pub sign: bool,
+}
+
+impl Default for Config {
+    /// Quiet forward subsumption reading standard input and writing
+    /// standard output, which is what an embedding usually wants.
+    fn default() -> Self {
+        Config {
+            input_path: "<stdin>".to_string(),
+            output_path: "<stdout>".to_string(),
+            mmap: false,
+            strict: false,
+            relaxed: false,
+            verbosity: -1,
+            backward_mode: false,
+            threads: 1,
+            combined_mode: false,
+            subsume_rounds: 16,
+            propagate: false,
+            probe: false,
+            probe_effort: 10,
+            substitute: false,
+            strengthen: false,
+            pure: false,
+            block: false,
+            block_limit: 1_000_000,
+            eliminate: false,
+            elim_occ_limit: 1000,
+            elim_clause_limit: 100,
+            frozen: Vec::new(),
+            proof_path: None,
+            binary_proof: false,
+            lrat: false,
+            compact: false,
+            compression_level: None,
+            sign: false,
+        }
+    }
+}
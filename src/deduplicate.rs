@@ -0,0 +1,69 @@
+use crate::formula::ClauseRef;
+use crate::preprocessor::SATContext;
+use crate::proof::delete_clause;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+fn clause_hash(literals: &[i32]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    literals.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Removes all but the first copy of every clause. Sorted copies of the
+/// literals are hashed so that equal clauses share a bucket, which keeps
+/// this close to linear in the size of the formula, while the clauses keep
+/// the order of their literals.
+pub(crate) fn deduplicate(ctx: &mut SATContext) {
+    verbose!(ctx.config.verbosity, 1, "starting duplicate clause removal");
+    let mut buckets: HashMap<u64, Vec<ClauseRef>> =
+        HashMap::with_capacity(ctx.formula.clauses.len());
+    let mut sorted = Vec::new();
+    let before = ctx.stats.duplicated;
+    for clause_id in ctx.formula.clauses.refs().to_vec() {
+        let formula = &mut ctx.formula;
+        if formula.clauses.is_garbage(clause_id) {
+            continue;
+        }
+        sorted.clear();
+        sorted.extend_from_slice(&formula.clauses[clause_id]);
+        sorted.sort_unstable();
+        let bucket = buckets.entry(clause_hash(&sorted)).or_default();
+        for &lit in &sorted {
+            formula.marks.mark(lit);
+        }
+        let clauses = &formula.clauses;
+        let marks = &formula.marks;
+        let kept = bucket
+            .iter()
+            .find(|&&other| {
+                clauses[other].len() == sorted.len()
+                    && clauses[other].iter().all(|&lit| marks.is_marked(lit))
+            })
+            .copied();
+        for &lit in &sorted {
+            formula.marks.unmark(lit);
+        }
+        match kept {
+            Some(kept) => {
+                LOG!(
+                    ctx.config.verbosity,
+                    "removing duplicated clause {:?}",
+                    &formula.clauses[clause_id]
+                );
+                formula.clauses.mark_used(kept);
+                delete_clause(ctx, clause_id);
+                ctx.stats.duplicated += 1;
+            }
+            None => bucket.push(clause_id),
+        }
+    }
+    verbose!(
+        ctx.config.verbosity,
+        1,
+        "removed {} duplicated clauses",
+        ctx.stats.duplicated - before
+    );
+    ctx.formula.collect_garbage_clauses(ctx.config.verbosity);
+}
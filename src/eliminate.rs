@@ -0,0 +1,162 @@
+use crate::extend::push_witness;
+use crate::formula::ClauseRef;
+use crate::preprocessor::SATContext;
+use crate::proof::delete_clause;
+use crate::subsume::backward_subsume;
+
+// Expects the antecedent containing 'pivot' to be marked without the pivot.
+fn resolve(ctx: &SATContext, c_id: ClauseRef, d_id: ClauseRef, pivot: i32) -> Option<Vec<i32>> {
+    let mut resolvent: Vec<i32> = ctx.formula.clauses[c_id]
+        .iter()
+        .copied()
+        .filter(|&lit| lit != pivot)
+        .collect();
+    for &lit in &ctx.formula.clauses[d_id] {
+        if lit == -pivot || ctx.formula.marks.is_marked(lit) {
+            continue;
+        }
+        if ctx.formula.marks.is_marked(-lit) {
+            return None;
+        }
+        resolvent.push(lit);
+    }
+    Some(resolvent)
+}
+
+// A resolvent together with its two antecedents.
+struct Resolvent {
+    literals: Vec<i32>,
+    antecedents: [ClauseRef; 2],
+}
+
+// Collects the non-tautological resolvents on 'var' or returns 'None' if
+// eliminating it would exceed the configured bounds.
+fn resolvents(
+    ctx: &mut SATContext,
+    var: i32,
+    pos: &[ClauseRef],
+    neg: &[ClauseRef],
+) -> Option<Vec<Resolvent>> {
+    let bound = pos.len() + neg.len();
+    let mut resolvents = Vec::new();
+    for &c_id in pos {
+        for &lit in &ctx.formula.clauses[c_id] {
+            if lit != var {
+                ctx.formula.marks.mark(lit);
+            }
+        }
+        let mut within_bounds = true;
+        for &d_id in neg {
+            if let Some(resolvent) = resolve(ctx, c_id, d_id, var) {
+                if resolvent.len() > ctx.config.elim_clause_limit || resolvents.len() == bound {
+                    within_bounds = false;
+                    break;
+                }
+                resolvents.push(Resolvent {
+                    literals: resolvent,
+                    antecedents: [c_id, d_id],
+                });
+            }
+        }
+        for &lit in &ctx.formula.clauses[c_id] {
+            ctx.formula.marks.unmark(lit);
+        }
+        if !within_bounds {
+            return None;
+        }
+    }
+    Some(resolvents)
+}
+
+fn try_eliminate(ctx: &mut SATContext, var: i32) {
+    ctx.formula.flush_garbage_occurrences(var);
+    ctx.formula.flush_garbage_occurrences(-var);
+    let pos = ctx.formula.matrix[var].clone();
+    let neg = ctx.formula.matrix[-var].clone();
+    if pos.is_empty() && neg.is_empty() {
+        return;
+    }
+    if pos.len() > ctx.config.elim_occ_limit || neg.len() > ctx.config.elim_occ_limit {
+        return;
+    }
+    let resolvents = match resolvents(ctx, var, &pos, &neg) {
+        Some(resolvents) => resolvents,
+        None => return,
+    };
+
+    LOG!(
+        ctx.config.verbosity,
+        "eliminating variable {} with {} resolvents",
+        var,
+        resolvents.len()
+    );
+    ctx.formula.eliminated[var as usize] = true;
+    ctx.stats.eliminated += 1;
+    // resolvents have to be in the proof before their antecedents go
+    let ids: Vec<u64> = resolvents.iter().map(|_| ctx.formula.next_id()).collect();
+    for (resolvent, &id) in resolvents.iter().zip(&ids) {
+        let hints = resolvent
+            .antecedents
+            .map(|clause_id| ctx.formula.clauses.id(clause_id));
+        ctx.formula.clauses.derive(id, &hints);
+        if let Some(proof) = &mut ctx.proof {
+            proof.add(id, &resolvent.literals, &hints);
+        }
+    }
+    for (&clause_id, witness) in pos
+        .iter()
+        .map(|id| (id, var))
+        .chain(neg.iter().map(|id| (id, -var)))
+    {
+        let clause = ctx.formula.clauses[clause_id].to_vec();
+        push_witness(ctx, witness, clause);
+        delete_clause(ctx, clause_id);
+    }
+
+    for (resolvent, id) in resolvents.into_iter().zip(ids) {
+        ctx.stats.resolvents += 1;
+        if resolvent.literals.is_empty() {
+            verbose!(ctx.config.verbosity, 2, "resolved empty clause");
+            ctx.formula.add_clause(id, &[], ctx.config.verbosity);
+            ctx.formula.empty_clause_exists = true;
+            return;
+        }
+        let clause_id = ctx
+            .formula
+            .add_clause(id, &resolvent.literals, ctx.config.verbosity);
+        backward_subsume(ctx, clause_id);
+        if ctx.formula.empty_clause_exists {
+            return;
+        }
+        ctx.formula.connect_clause(clause_id, ctx.config.verbosity);
+    }
+}
+
+/// Bounded variable elimination: resolves away variables as long as this
+/// does not increase the number of clauses.
+pub(crate) fn eliminate(ctx: &mut SATContext) {
+    verbose!(ctx.config.verbosity, 1, "starting variable elimination");
+    ctx.formula.connect_all_clauses(ctx.config.verbosity);
+
+    let mut schedule: Vec<i32> = (1..=ctx.formula.variables as i32)
+        .filter(|&var| {
+            !ctx.formula.eliminated[var as usize] && !ctx.formula.is_frozen(var as usize)
+        })
+        .collect();
+    schedule.sort_by_key(|&var| ctx.formula.matrix[var].len() + ctx.formula.matrix[-var].len());
+
+    let before = ctx.stats.eliminated;
+    for var in schedule {
+        try_eliminate(ctx, var);
+        if ctx.formula.empty_clause_exists {
+            break;
+        }
+    }
+    verbose!(
+        ctx.config.verbosity,
+        1,
+        "eliminated {} variables",
+        ctx.stats.eliminated - before
+    );
+    ctx.formula.collect_garbage_clauses(ctx.config.verbosity);
+}
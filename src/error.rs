@@ -0,0 +1,141 @@
+use std::fmt;
+use std::io;
+
+/// Everything that can go wrong while reading, simplifying or writing a
+/// formula.
+#[derive(Debug)]
+pub enum BabysubError {
+    /// Reading or writing a plain file or stream failed.
+    Io(io::Error),
+    /// A file could not be opened for reading or created for writing.
+    File {
+        path: String,
+        action: &'static str,
+        source: io::Error,
+    },
+    /// The input is not valid DIMACS.
+    Parse {
+        path: String,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// The clauses do not fit the `p cnf` header.
+    HeaderMismatch {
+        path: String,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// A compressed stream could not be decoded or encoded.
+    Compression {
+        format: &'static str,
+        source: io::Error,
+    },
+    /// A file named like a compressed file contains something else.
+    FormatMismatch {
+        path: String,
+        expected: &'static str,
+        found: &'static str,
+    },
+    /// The formula exceeds what babysub can represent.
+    ResourceLimit(String),
+    /// A clause cannot be removed, as other clauses were simplified with it.
+    ClauseInUse(Vec<i32>),
+}
+
+pub type Result<T> = std::result::Result<T, BabysubError>;
+
+impl BabysubError {
+    // Attributes a failure on a possibly compressed stream to its codec.
+    pub(crate) fn stream(compression: Option<&'static str>, e: io::Error) -> Self {
+        match compression {
+            Some(format) => BabysubError::Compression { format, source: e },
+            None => BabysubError::Io(e),
+        }
+    }
+
+    pub(crate) fn open(path: &str, e: io::Error) -> Self {
+        BabysubError::File {
+            path: path.to_string(),
+            action: "open input file",
+            source: e,
+        }
+    }
+
+    pub(crate) fn create(path: &str, e: io::Error) -> Self {
+        BabysubError::File {
+            path: path.to_string(),
+            action: "create output file",
+            source: e,
+        }
+    }
+}
+
+impl fmt::Display for BabysubError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BabysubError::Io(e) => write!(f, "{}", e),
+            BabysubError::File {
+                path,
+                action,
+                source,
+            } => write!(f, "Failed to {} '{}': {}", action, path, source),
+            BabysubError::Parse {
+                path,
+                line,
+                column,
+                message,
+            } => write!(
+                f,
+                "at line {} column {} in '{}': {}",
+                line, column, path, message
+            ),
+            BabysubError::HeaderMismatch {
+                path,
+                line,
+                column,
+                message,
+            } => write!(
+                f,
+                "at line {} column {} in '{}': {}",
+                line, column, path, message
+            ),
+            BabysubError::Compression { format, source } => {
+                write!(f, "{} compression failed: {}", format, source)
+            }
+            BabysubError::FormatMismatch {
+                path,
+                expected,
+                found,
+            } => write!(
+                f,
+                "'{}' is named like a {} file but contains {} data",
+                path, expected, found
+            ),
+            BabysubError::ResourceLimit(message) => write!(f, "{}", message),
+            BabysubError::ClauseInUse(literals) => write!(
+                f,
+                "clause {:?} was used to simplify other clauses and cannot be removed",
+                literals
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BabysubError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BabysubError::Io(e) => Some(e),
+            BabysubError::File { source, .. } => Some(source),
+            BabysubError::Compression { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for BabysubError {
+    fn from(e: io::Error) -> Self {
+        BabysubError::Io(e)
+    }
+}
@@ -0,0 +1,272 @@
+use crate::error::{BabysubError, Result};
+use crate::parse::open_input;
+use crate::preprocessor::SATContext;
+use crate::print::open_output;
+use std::io::{BufRead, BufReader, Write};
+
+/// A clause removed by a simplification which only preserves
+/// satisfiability, together with the literal to flip if a model of the
+/// simplified formula falsifies it.
+#[derive(Debug, Clone)]
+pub(crate) struct Witness {
+    pub(crate) lit: i32,
+    pub(crate) clause: Vec<i32>,
+}
+
+pub(crate) fn push_witness(ctx: &mut SATContext, lit: i32, clause: Vec<i32>) {
+    LOG!(
+        ctx.config.verbosity,
+        "pushing witness {} for clause {:?}",
+        lit,
+        clause
+    );
+    ctx.formula.extension.push(Witness { lit, clause });
+}
+
+fn satisfied(values: &[bool], clause: &[i32]) -> bool {
+    clause
+        .iter()
+        .any(|&lit| values[lit.unsigned_abs() as usize] == (lit > 0))
+}
+
+// Walks the extension stack from the most recently removed clause back to
+// the first one and flips the witness of every falsified clause.
+fn extend_values(variables: usize, stack: &[Witness], model: &[i32], _verbosity: i32) -> Vec<i32> {
+    let mut values = vec![false; variables + 1];
+    for &lit in model {
+        let var = lit.unsigned_abs() as usize;
+        if var <= variables {
+            values[var] = lit > 0;
+        }
+    }
+    let mut flipped = 0;
+    for witness in stack.iter().rev() {
+        if !satisfied(&values, &witness.clause) {
+            LOG!(
+                _verbosity,
+                "flipping {} to satisfy {:?}",
+                witness.lit,
+                witness.clause
+            );
+            values[witness.lit.unsigned_abs() as usize] = witness.lit > 0;
+            flipped += 1;
+        }
+    }
+    verbose!(
+        _verbosity,
+        1,
+        "extended model flipping {} literals",
+        flipped
+    );
+    (1..=variables)
+        .map(|var| {
+            if values[var] {
+                var as i32
+            } else {
+                -(var as i32)
+            }
+        })
+        .collect()
+}
+
+pub(crate) fn extend_model(ctx: &SATContext, model: &[i32]) -> Vec<i32> {
+    extend_values(
+        ctx.formula.variables,
+        &ctx.formula.extension,
+        model,
+        ctx.config.verbosity,
+    )
+}
+
+fn write_stack(output: &mut dyn Write, variables: usize, stack: &[Witness]) -> std::io::Result<()> {
+    writeln!(output, "c witness literal first, in the order removed")?;
+    writeln!(output, "p reconstruction {} {}", variables, stack.len())?;
+    for witness in stack {
+        write!(output, "{}", witness.lit)?;
+        for &lit in &witness.clause {
+            if lit != witness.lit {
+                write!(output, " {}", lit)?;
+            }
+        }
+        writeln!(output, " 0")?;
+    }
+    output.flush()
+}
+
+pub(crate) fn write_reconstruction(ctx: &SATContext, path: &str) -> Result<()> {
+    verbose!(
+        ctx.config.verbosity,
+        1,
+        "writing {} reconstruction entries to '{}'",
+        ctx.formula.extension.len(),
+        path
+    );
+    let (mut output, compression) = open_output(path, ctx.config.compression_level)?;
+    write_stack(&mut output, ctx.formula.variables, &ctx.formula.extension)
+        .and_then(|()| output.finish())
+        .map_err(|e| BabysubError::stream(compression, e))
+}
+
+fn parse_error(path: &str, line: usize, column: usize, message: &str) -> BabysubError {
+    BabysubError::Parse {
+        path: path.to_string(),
+        line,
+        column,
+        message: message.to_string(),
+    }
+}
+
+// Reads the non-zero literals of a line from 'start' up to the terminating
+// zero and reports whether that zero was found.
+fn parse_literals(
+    path: &str,
+    line_number: usize,
+    line: &str,
+    start: usize,
+) -> Result<(Vec<i32>, bool)> {
+    let mut literals = Vec::new();
+    for token in line[start..].split_whitespace() {
+        let column = token.as_ptr() as usize - line.as_ptr() as usize + 1;
+        let lit: i32 = token
+            .parse()
+            .map_err(|_| parse_error(path, line_number, column, "Invalid literal format."))?;
+        if lit == 0 {
+            return Ok((literals, true));
+        }
+        literals.push(lit);
+    }
+    Ok((literals, false))
+}
+
+/// The clauses removed by satisfiability preserving simplifications in the
+/// order they were removed, which is all that is needed to turn a model of
+/// the simplified formula into a model of the original one.
+#[derive(Debug, Clone, Default)]
+pub struct Reconstruction {
+    variables: usize,
+    stack: Vec<Witness>,
+}
+
+impl Reconstruction {
+    /// Reads a reconstruction file written with
+    /// [`Preprocessor::write_reconstruction`](crate::Preprocessor::write_reconstruction).
+    pub fn read(path: &str, verbosity: i32) -> Result<Self> {
+        let (input, compression) = open_input(path, verbosity)?;
+        let mut reconstruction = Reconstruction::default();
+        let mut header_parsed = false;
+        for (index, line) in BufReader::new(input).lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.map_err(|e| BabysubError::stream(compression, e))?;
+            if line.starts_with('c') || line.trim().is_empty() {
+                continue;
+            }
+            if let Some(header) = line.strip_prefix("p reconstruction") {
+                let variables = header
+                    .split_whitespace()
+                    .next()
+                    .and_then(|v| v.parse().ok());
+                reconstruction.variables = variables.ok_or_else(|| {
+                    parse_error(path, line_number, 1, "Invalid reconstruction header.")
+                })?;
+                header_parsed = true;
+                continue;
+            }
+            if !header_parsed {
+                return Err(parse_error(
+                    path,
+                    line_number,
+                    1,
+                    "Reconstruction header not found.",
+                ));
+            }
+            let (clause, terminated) = parse_literals(path, line_number, &line, 0)?;
+            if !terminated || clause.is_empty() {
+                return Err(parse_error(
+                    path,
+                    line_number,
+                    line.len() + 1,
+                    "Expected witness and clause terminated by zero.",
+                ));
+            }
+            if let Some(&lit) = clause
+                .iter()
+                .find(|lit| lit.unsigned_abs() as usize > reconstruction.variables)
+            {
+                let column = line
+                    .split_whitespace()
+                    .find(|token| token.parse() == Ok(lit))
+                    .map_or(1, |token| {
+                        token.as_ptr() as usize - line.as_ptr() as usize + 1
+                    });
+                return Err(BabysubError::HeaderMismatch {
+                    path: path.to_string(),
+                    line: line_number,
+                    column,
+                    message: format!(
+                        "literal {} exceeds the {} declared variables",
+                        lit, reconstruction.variables
+                    ),
+                });
+            }
+            reconstruction.stack.push(Witness {
+                lit: clause[0],
+                clause,
+            });
+        }
+        Ok(reconstruction)
+    }
+
+    /// Number of variables of the original formula.
+    pub fn variables(&self) -> usize {
+        self.variables
+    }
+
+    /// Number of removed clauses on the stack.
+    pub fn len(&self) -> usize {
+        self.stack.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stack.is_empty()
+    }
+
+    /// Extends a model of the simplified formula, given as DIMACS literals,
+    /// to a model of the original formula with one literal per variable.
+    pub fn extend(&self, model: &[i32]) -> Vec<i32> {
+        extend_values(self.variables, &self.stack, model, -1)
+    }
+}
+
+/// Reads a solution in SAT competition format, `s SATISFIABLE` followed by
+/// `v` lines, and returns `None` if it reports `s UNSATISFIABLE`.
+pub fn read_solution(path: &str, verbosity: i32) -> Result<Option<Vec<i32>>> {
+    let (input, compression) = open_input(path, verbosity)?;
+    let mut status = None;
+    let mut model = Vec::new();
+    for (index, line) in BufReader::new(input).lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.map_err(|e| BabysubError::stream(compression, e))?;
+        if let Some(result) = line.strip_prefix("s ") {
+            status = match result.trim() {
+                "SATISFIABLE" => Some(true),
+                "UNSATISFIABLE" => Some(false),
+                _ => {
+                    return Err(parse_error(
+                        path,
+                        line_number,
+                        3,
+                        "Expected 'SATISFIABLE' or 'UNSATISFIABLE'.",
+                    ))
+                }
+            };
+        } else if line.starts_with('v') {
+            let (literals, _) = parse_literals(path, line_number, &line, 1)?;
+            model.extend(literals);
+        }
+    }
+    match status {
+        Some(true) => Ok(Some(model)),
+        Some(false) => Ok(None),
+        None => Err(parse_error(path, 1, 1, "Solution status line not found.")),
+    }
+}
@@ -0,0 +1,705 @@
+use crate::extend::Witness;
+use std::collections::{HashMap, HashSet};
+use std::ops::{Index, IndexMut};
+
+/// Offset of a clause header in the arena. References stay valid until
+/// garbage is collected, which remaps the occurrence lists.
+pub(crate) type ClauseRef = usize;
+
+// Every clause is stored as a header followed by its literals. The header
+// holds the flags, the size, the proof id and the signature, the two 64-bit
+// fields split into two words each.
+const FLAGS: usize = 0;
+const SIZE: usize = 1;
+const ID: usize = 2;
+const SIGNATURE: usize = 4;
+const HEADER: usize = 6;
+
+const GARBAGE: i32 = 1;
+// Set on clauses copied by the garbage collector, whose id words then hold
+// the new reference.
+const MOVED: i32 = 2;
+// Set on clauses added or shrunken since they were last checked for
+// subsumption.
+const DIRTY: i32 = 4;
+// Set on clauses which other clauses were simplified with, so that removing
+// them would leave those simplifications unjustified.
+const USED: i32 = 8;
+
+fn read64(arena: &[i32], offset: usize) -> u64 {
+    arena[offset] as u32 as u64 | (arena[offset + 1] as u32 as u64) << 32
+}
+
+fn write64(arena: &mut [i32], offset: usize, value: u64) {
+    arena[offset] = value as u32 as i32;
+    arena[offset + 1] = (value >> 32) as u32 as i32;
+}
+
+// Which clauses were derived from which, by id, so that removing a clause
+// can tell whether the formula still depends on it.
+#[derive(Default)]
+struct Derivations {
+    // Ids of the clauses derived from each antecedent.
+    derived: HashMap<u64, Vec<u64>>,
+    // Ids of antecedents which left the formula, by their sorted literals.
+    removed: HashMap<Vec<i32>, Vec<u64>>,
+}
+
+impl Derivations {
+    fn derive(&mut self, id: u64, antecedents: &[u64]) {
+        for &antecedent in antecedents {
+            self.derived.entry(antecedent).or_default().push(id);
+        }
+    }
+
+    // Keeps the literals of a clause leaving the formula if it has been an
+    // antecedent, as it could not be found by them otherwise.
+    fn remove(&mut self, id: u64, literals: &[i32]) {
+        if self.derived.contains_key(&id) {
+            let mut literals = literals.to_vec();
+            literals.sort_unstable();
+            self.removed.entry(literals).or_default().push(id);
+        }
+    }
+
+    // Whether one of the 'live' ids is derived from one of 'ids', directly or
+    // through clauses derived in between.
+    fn reaches(&self, ids: &[u64], live: &HashSet<u64>) -> bool {
+        let mut visited = HashSet::new();
+        let mut stack: Vec<u64> = ids.to_vec();
+        while let Some(id) = stack.pop() {
+            for &derived in self.derived.get(&id).into_iter().flatten() {
+                if live.contains(&derived) {
+                    return true;
+                }
+                if visited.insert(derived) {
+                    stack.push(derived);
+                }
+            }
+        }
+        false
+    }
+}
+
+/// All clauses of a formula in one contiguous arena, together with the
+/// order in which they are visited.
+pub(crate) struct Clauses {
+    arena: Vec<i32>,
+    refs: Vec<ClauseRef>,
+    // Words no longer used by garbage or shrunken clauses.
+    wasted: usize,
+    // Clauses not marked as garbage.
+    live: usize,
+    derivations: Derivations,
+}
+
+impl Clauses {
+    fn new() -> Self {
+        Clauses {
+            arena: Vec::new(),
+            refs: Vec::new(),
+            wasted: 0,
+            live: 0,
+            derivations: Derivations::default(),
+        }
+    }
+
+    fn size(&self, clause: ClauseRef) -> usize {
+        self.arena[clause + SIZE] as usize
+    }
+
+    /// Number of clauses including those not yet collected.
+    pub(crate) fn len(&self) -> usize {
+        self.refs.len()
+    }
+
+    /// Number of clauses not marked as garbage.
+    pub(crate) fn live(&self) -> usize {
+        self.live
+    }
+
+    /// The clauses in visiting order.
+    pub(crate) fn refs(&self) -> &[ClauseRef] {
+        &self.refs
+    }
+
+    pub(crate) fn is_garbage(&self, clause: ClauseRef) -> bool {
+        self.arena[clause + FLAGS] & GARBAGE != 0
+    }
+
+    pub(crate) fn mark_garbage(&mut self, clause: ClauseRef) {
+        debug_assert!(!self.is_garbage(clause));
+        let id = self.id(clause);
+        let start = clause + HEADER;
+        let literals = &self.arena[start..start + self.size(clause)];
+        self.derivations.remove(id, literals);
+        self.arena[clause + FLAGS] |= GARBAGE;
+        self.wasted += HEADER + self.size(clause);
+        self.live -= 1;
+    }
+
+    pub(crate) fn is_dirty(&self, clause: ClauseRef) -> bool {
+        self.arena[clause + FLAGS] & DIRTY != 0
+    }
+
+    pub(crate) fn clear_dirty(&mut self, clause: ClauseRef) {
+        self.arena[clause + FLAGS] &= !DIRTY;
+    }
+
+    pub(crate) fn is_used(&self, clause: ClauseRef) -> bool {
+        self.arena[clause + FLAGS] & USED != 0
+    }
+
+    pub(crate) fn mark_used(&mut self, clause: ClauseRef) {
+        self.arena[clause + FLAGS] |= USED;
+    }
+
+    pub(crate) fn clear_all_dirty(&mut self) {
+        for &clause in &self.refs {
+            self.arena[clause + FLAGS] &= !DIRTY;
+        }
+    }
+
+    // Persistent id used in proofs, unlike references which change whenever
+    // garbage is collected.
+    pub(crate) fn id(&self, clause: ClauseRef) -> u64 {
+        read64(&self.arena, clause + ID)
+    }
+
+    // Literal bloom filter, see 'signature'.
+    pub(crate) fn signature(&self, clause: ClauseRef) -> u64 {
+        read64(&self.arena, clause + SIGNATURE)
+    }
+
+    pub(crate) fn push(&mut self, id: u64, literals: &[i32]) -> ClauseRef {
+        let clause = self.start(id);
+        self.arena.extend_from_slice(literals);
+        self.finish(clause);
+        clause
+    }
+
+    // Starts a clause at the end of the arena, whose literals are appended
+    // one by one until it is finished or discarded.
+    pub(crate) fn start(&mut self, id: u64) -> ClauseRef {
+        let clause = self.arena.len();
+        self.arena.extend_from_slice(&[0; HEADER]);
+        self.arena[clause + FLAGS] = DIRTY;
+        write64(&mut self.arena, clause + ID, id);
+        clause
+    }
+
+    pub(crate) fn push_literal(&mut self, lit: i32) {
+        self.arena.push(lit);
+    }
+
+    // Literals appended to the started clause so far.
+    pub(crate) fn pending(&self, clause: ClauseRef) -> &[i32] {
+        &self.arena[clause + HEADER..]
+    }
+
+    pub(crate) fn finish(&mut self, clause: ClauseRef) {
+        let size = self.arena.len() - clause - HEADER;
+        self.arena[clause + SIZE] = size as i32;
+        let signature = signature(self.pending(clause));
+        write64(&mut self.arena, clause + SIGNATURE, signature);
+        self.refs.push(clause);
+        self.live += 1;
+    }
+
+    pub(crate) fn discard(&mut self, clause: ClauseRef) {
+        self.arena.truncate(clause);
+    }
+
+    // Records that the clause 'id' is derived from the 'antecedents'.
+    pub(crate) fn derive(&mut self, id: u64, antecedents: &[u64]) {
+        self.derivations.derive(id, antecedents);
+    }
+
+    // Whether a live clause is derived from one of the clauses 'ids'.
+    pub(crate) fn has_live_derived(&self, ids: &[u64]) -> bool {
+        let live: HashSet<u64> = self
+            .refs
+            .iter()
+            .filter(|&&clause| !self.is_garbage(clause))
+            .map(|&clause| self.id(clause))
+            .collect();
+        self.derivations.reaches(ids, &live)
+    }
+
+    // Ids of the antecedents with the given sorted literals which are no
+    // longer in the formula.
+    pub(crate) fn removed_antecedents(&self, literals: &[i32]) -> &[u64] {
+        self.derivations
+            .removed
+            .get(literals)
+            .map_or(&[], Vec::as_slice)
+    }
+
+    // Overwrites the literals in place with at most as many new ones, which
+    // are derived from the 'antecedents' and therefore get a new id.
+    pub(crate) fn replace(
+        &mut self,
+        clause: ClauseRef,
+        id: u64,
+        literals: &[i32],
+        antecedents: &[u64],
+    ) {
+        let size = self.size(clause);
+        assert!(literals.len() <= size, "replaced clause grows");
+        self.derivations.derive(id, antecedents);
+        let start = clause + HEADER;
+        let original = self.id(clause);
+        self.derivations
+            .remove(original, &self.arena[start..start + size]);
+        self.arena[start..start + literals.len()].copy_from_slice(literals);
+        self.arena[clause + FLAGS] |= DIRTY;
+        self.arena[clause + SIZE] = literals.len() as i32;
+        self.wasted += size - literals.len();
+        write64(&mut self.arena, clause + ID, id);
+        write64(&mut self.arena, clause + SIGNATURE, signature(literals));
+    }
+
+    // Stable, so clauses of equal key keep their order.
+    fn sort_by_key<K: Ord>(&mut self, key: impl Fn(&[i32]) -> K) {
+        let arena = &self.arena;
+        self.refs.sort_by_key(|&clause| {
+            let start = clause + HEADER;
+            key(&arena[start..start + arena[clause + SIZE] as usize])
+        });
+    }
+
+    // Drops all clauses, but not their derivations.
+    pub(crate) fn clear(&mut self) {
+        self.arena.clear();
+        self.refs.clear();
+        self.wasted = 0;
+        self.live = 0;
+    }
+
+    // Copies the live clauses in visiting order into a new arena and maps
+    // the references in 'matrix' to the copies, dropping garbage.
+    fn collect_garbage(&mut self, matrix: &mut Matrix) {
+        let mut arena = Vec::with_capacity(self.arena.len() - self.wasted);
+        let mut refs = Vec::with_capacity(self.refs.len());
+        for position in 0..self.refs.len() {
+            let clause = self.refs[position];
+            if self.is_garbage(clause) {
+                continue;
+            }
+            let moved = arena.len();
+            arena.extend_from_slice(&self.arena[clause..clause + HEADER + self.size(clause)]);
+            refs.push(moved);
+            // the old copy forwards to the new one until the matrix is mapped
+            self.arena[clause + FLAGS] |= MOVED;
+            write64(&mut self.arena, clause + ID, moved as u64);
+        }
+        let old = std::mem::replace(&mut self.arena, arena);
+        matrix.remap(|clause| {
+            if old[clause + FLAGS] & MOVED != 0 {
+                Some(read64(&old, clause + ID) as ClauseRef)
+            } else {
+                None
+            }
+        });
+        self.refs = refs;
+        self.wasted = 0;
+    }
+}
+
+impl Index<ClauseRef> for Clauses {
+    type Output = [i32];
+
+    fn index(&self, clause: ClauseRef) -> &Self::Output {
+        let start = clause + HEADER;
+        &self.arena[start..start + self.size(clause)]
+    }
+}
+
+impl IndexMut<ClauseRef> for Clauses {
+    fn index_mut(&mut self, clause: ClauseRef) -> &mut Self::Output {
+        let start = clause + HEADER;
+        let end = start + self.size(clause);
+        &mut self.arena[start..end]
+    }
+}
+
+// Every literal sets one of 64 bits, with both literals of a variable on
+// adjacent bits. The literals of a clause can only be a subset of another's
+// if the same holds for their signatures.
+pub(crate) fn signature(literals: &[i32]) -> u64 {
+    literals.iter().fold(0, |signature, &lit| {
+        signature | 1 << (2 * (lit.unsigned_abs() % 32) + (lit < 0) as u32)
+    })
+}
+
+// Projects a literal signature onto variables, as needed for strengthening
+// where one literal occurs negated.
+pub(crate) fn variable_signature(signature: u64) -> u64 {
+    (signature | signature >> 1) & 0x5555_5555_5555_5555
+}
+
+pub(crate) struct Matrix {
+    matrix: Vec<Vec<ClauseRef>>,
+}
+
+impl Matrix {
+    fn new() -> Self {
+        Matrix { matrix: Vec::new() }
+    }
+
+    fn map_literal_to_index(&self, literal: i32) -> usize {
+        // Optimization for matrix indexing
+        // With this, lit and -lit will be next to each other
+        if literal < 0 {
+            (-literal * 2 - 2) as usize
+        } else {
+            (literal * 2 - 1) as usize
+        }
+    }
+
+    pub(crate) fn init(&mut self, variables: usize, _verbosity: i32) {
+        LOG!(
+            _verbosity,
+            "initializing matrix with {} variables",
+            variables
+        );
+        self.matrix = vec![Vec::new(); 2 * variables];
+    }
+
+    fn resize(&mut self, variables: usize) {
+        self.matrix.resize(2 * variables, Vec::new());
+    }
+
+    // Replaces every clause reference by 'map' of it, dropping those mapped
+    // to 'None'.
+    fn remap(&mut self, map: impl Fn(ClauseRef) -> Option<ClauseRef>) {
+        for occurrences in &mut self.matrix {
+            occurrences.retain_mut(|clause| match map(*clause) {
+                Some(moved) => {
+                    *clause = moved;
+                    true
+                }
+                None => false,
+            });
+        }
+    }
+}
+
+impl Index<i32> for Matrix {
+    type Output = Vec<ClauseRef>;
+
+    fn index(&self, literal: i32) -> &Self::Output {
+        let computed_index = self.map_literal_to_index(literal);
+        assert!(
+            computed_index < self.matrix.len(),
+            "Matrix index out of bounds"
+        );
+        &self.matrix[computed_index]
+    }
+}
+
+impl IndexMut<i32> for Matrix {
+    fn index_mut(&mut self, literal: i32) -> &mut Self::Output {
+        let computed_index = self.map_literal_to_index(literal);
+        assert!(
+            computed_index < self.matrix.len(),
+            "Matrix index out of bounds"
+        );
+        &mut self.matrix[computed_index]
+    }
+}
+
+pub(crate) struct Marks {
+    marks: Vec<bool>,
+}
+
+impl Marks {
+    pub(crate) fn new() -> Self {
+        Marks { marks: Vec::new() }
+    }
+
+    fn map_literal_to_index(&self, literal: i32) -> usize {
+        // Optimization for indexing
+        // With this, lit and -lit will be next to each other
+        if literal < 0 {
+            (-literal * 2 - 2) as usize
+        } else {
+            (literal * 2 - 1) as usize
+        }
+    }
+
+    pub(crate) fn init(&mut self, variables: usize, _verbosity: i32) {
+        LOG!(
+            _verbosity,
+            "initializing marks with {} variables",
+            variables
+        );
+        self.marks = vec![false; 2 * variables];
+    }
+
+    fn resize(&mut self, variables: usize) {
+        self.marks.resize(2 * variables, false);
+    }
+
+    pub(crate) fn mark(&mut self, literal: i32) {
+        let computed_index = self.map_literal_to_index(literal);
+        assert!(
+            computed_index < self.marks.len(),
+            "Marks index out of bounds"
+        );
+        self.marks[computed_index] = true;
+    }
+
+    pub(crate) fn unmark(&mut self, literal: i32) {
+        let computed_index = self.map_literal_to_index(literal);
+        assert!(
+            computed_index < self.marks.len(),
+            "Marks index out of bounds"
+        );
+        self.marks[computed_index] = false;
+    }
+
+    pub(crate) fn is_marked(&self, literal: i32) -> bool {
+        let computed_index = self.map_literal_to_index(literal);
+        assert!(
+            computed_index < self.marks.len(),
+            "Marks index out of bounds"
+        );
+        self.marks[computed_index]
+    }
+}
+
+/// The clauses of a formula together with the occurrence lists and marks
+/// used while simplifying it.
+pub struct CNFFormula {
+    pub(crate) variables: usize,
+    pub(crate) clauses: Clauses,
+    pub(crate) empty_clause_exists: bool,
+    pub(crate) matrix: Matrix,
+    pub(crate) marks: Marks,
+    pub(crate) eliminated: Vec<bool>,
+    // Variables which must stay in the formula. Only grows, independent of
+    // the number of variables, as variables can be frozen before parsing.
+    frozen: Vec<bool>,
+    // Root-level assignment of each variable: 1 true, -1 false, 0 unassigned.
+    pub(crate) values: Vec<i8>,
+    // Id of the unit clause each assigned variable was propagated from.
+    pub(crate) reasons: Vec<u64>,
+    pub(crate) trail: Vec<i32>,
+    // Trail literals whose clauses have been simplified by propagation.
+    pub(crate) propagated: usize,
+    pub(crate) extension: Vec<Witness>,
+    // Whether all clauses which are not dirty have been simplified together,
+    // so that simplifying again only has to check the dirty ones.
+    pub(crate) simplified: bool,
+    // Whether every live clause is connected to the occurrence lists of all
+    // its literals, which incremental simplification keeps up to date.
+    pub(crate) connected: bool,
+    // Input clauses are numbered from one, derived clauses continue after.
+    last_id: u64,
+}
+
+impl CNFFormula {
+    pub(crate) fn new() -> Self {
+        CNFFormula {
+            variables: 0,
+            clauses: Clauses::new(),
+            empty_clause_exists: false,
+            matrix: Matrix::new(),
+            marks: Marks::new(),
+            eliminated: vec![false],
+            frozen: Vec::new(),
+            values: vec![0],
+            reasons: vec![0],
+            trail: Vec::new(),
+            propagated: 0,
+            extension: Vec::new(),
+            simplified: false,
+            connected: false,
+            last_id: 0,
+        }
+    }
+
+    /// Number of variables the formula ranges over.
+    pub fn variables(&self) -> usize {
+        self.variables
+    }
+
+    /// Number of clauses currently in the formula.
+    pub fn len(&self) -> usize {
+        self.clauses.live()
+    }
+
+    /// Whether the formula has no clauses left.
+    pub fn is_empty(&self) -> bool {
+        self.clauses.live() == 0
+    }
+
+    /// Iterates over the literals of all clauses in the formula.
+    pub fn clauses(&self) -> impl Iterator<Item = &[i32]> {
+        self.clauses
+            .refs()
+            .iter()
+            .filter(|&&clause| !self.clauses.is_garbage(clause))
+            .map(|&clause| &self.clauses[clause])
+    }
+
+    pub(crate) fn init(&mut self, variables: usize, verbosity: i32) {
+        self.variables = variables;
+        self.marks.init(variables, verbosity);
+        self.matrix.init(variables, verbosity);
+        self.eliminated = vec![false; variables + 1];
+        self.values = vec![0; variables + 1];
+        self.reasons = vec![0; variables + 1];
+    }
+
+    pub(crate) fn resize(&mut self, variables: usize, _verbosity: i32) {
+        LOG!(_verbosity, "resizing formula to {} variables", variables);
+        self.variables = variables;
+        self.marks.resize(variables);
+        self.matrix.resize(variables);
+        self.eliminated.resize(variables + 1, false);
+        self.values.resize(variables + 1, 0);
+        self.reasons.resize(variables + 1, 0);
+    }
+
+    pub(crate) fn freeze(&mut self, var: usize) {
+        if var >= self.frozen.len() {
+            self.frozen.resize(var + 1, false);
+        }
+        self.frozen[var] = true;
+    }
+
+    pub(crate) fn is_frozen(&self, var: usize) -> bool {
+        self.frozen.get(var).is_some_and(|&frozen| frozen)
+    }
+
+    pub(crate) fn value(&self, lit: i32) -> i8 {
+        let value = self.values[lit.unsigned_abs() as usize];
+        if lit < 0 {
+            -value
+        } else {
+            value
+        }
+    }
+
+    pub(crate) fn next_id(&mut self) -> u64 {
+        self.last_id += 1;
+        self.last_id
+    }
+
+    pub(crate) fn add_clause(&mut self, id: u64, clause: &[i32], verbosity: i32) -> ClauseRef {
+        let clause_id = self.clauses.start(id);
+        for &lit in clause {
+            self.clauses.push_literal(lit);
+        }
+        self.finish_clause(clause_id, verbosity);
+        clause_id
+    }
+
+    // Adds the clause built in the arena since it was started.
+    pub(crate) fn finish_clause(&mut self, clause_id: ClauseRef, _verbosity: i32) {
+        self.clauses.finish(clause_id);
+        LOG!(
+            _verbosity,
+            "adding clause {}: {:?}",
+            self.clauses.id(clause_id),
+            &self.clauses[clause_id]
+        );
+        if self.connected {
+            self.connect_clause(clause_id, _verbosity);
+        }
+    }
+
+    pub(crate) fn connect_lit(&mut self, lit: i32, clause_id: ClauseRef, _verbosity: i32) {
+        LOG!(
+            _verbosity,
+            "connecting literal {} to clause {}",
+            lit,
+            clause_id
+        );
+        self.matrix[lit].push(clause_id);
+    }
+
+    pub(crate) fn connect_clause(&mut self, clause_id: ClauseRef, _verbosity: i32) {
+        LOG!(_verbosity, "connecting clause {}", clause_id);
+        for &lit in &self.clauses[clause_id] {
+            self.matrix[lit].push(clause_id);
+        }
+    }
+
+    // Rebuilds full occurrence lists of all live clauses.
+    pub(crate) fn connect_all_clauses(&mut self, verbosity: i32) {
+        self.matrix.init(self.variables, verbosity);
+        for &clause_id in self.clauses.refs() {
+            if !self.clauses.is_garbage(clause_id) {
+                for &lit in &self.clauses[clause_id] {
+                    self.matrix[lit].push(clause_id);
+                }
+            }
+        }
+    }
+
+    // Finds a live clause with exactly the given literals, which have to be
+    // free of duplicates, using the occurrence lists if they are complete.
+    pub(crate) fn find_clause(&mut self, literals: &[i32]) -> Option<ClauseRef> {
+        if literals
+            .iter()
+            .any(|lit| lit.unsigned_abs() as usize > self.variables)
+        {
+            return None;
+        }
+        for &lit in literals {
+            self.marks.mark(lit);
+        }
+        let clauses = &self.clauses;
+        let marks = &self.marks;
+        let matches = |&&clause_id: &&ClauseRef| {
+            !clauses.is_garbage(clause_id)
+                && clauses[clause_id].len() == literals.len()
+                && clauses[clause_id].iter().all(|&lit| marks.is_marked(lit))
+        };
+        let found = match literals.first() {
+            Some(&lit) if self.connected => self.matrix[lit].iter().find(matches),
+            _ => self.clauses.refs().iter().find(matches),
+        }
+        .copied();
+        for &lit in literals {
+            self.marks.unmark(lit);
+        }
+        found
+    }
+
+    // Drops clauses which no longer contain 'lit' from its occurrence list.
+    pub(crate) fn flush_occurrences(&mut self, lit: i32, _verbosity: i32) {
+        LOG!(_verbosity, "flushing occurrences of literal {}", lit);
+        let clauses = &self.clauses;
+        self.matrix[lit].retain(|&clause_id| clauses[clause_id].contains(&lit));
+    }
+
+    pub(crate) fn flush_garbage_occurrences(&mut self, lit: i32) {
+        let clauses = &self.clauses;
+        self.matrix[lit].retain(|&clause_id| !clauses.is_garbage(clause_id));
+    }
+
+    // Sorts the clauses and moves them in the arena into the new order, so
+    // that visiting them in order stays cache friendly.
+    pub(crate) fn sort_clauses_by_key<K: Ord>(&mut self, key: impl Fn(&[i32]) -> K) {
+        self.clauses.sort_by_key(key);
+        self.clauses.collect_garbage(&mut self.matrix);
+    }
+
+    // Compacts the clause arena, keeping the occurrence lists valid.
+    pub(crate) fn collect_garbage_clauses(&mut self, _verbosity: i32) {
+        if self.clauses.wasted == 0 {
+            return;
+        }
+        let _before = self.clauses.len();
+        self.clauses.collect_garbage(&mut self.matrix);
+        LOG!(
+            _verbosity,
+            "collected garbage: {} clauses",
+            _before - self.clauses.len()
+        );
+    }
+}
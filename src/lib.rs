@@ -0,0 +1,94 @@
+//! An educational SAT preprocessor.
+//!
+//! The [`Preprocessor`] loads a formula in DIMACS CNF format (or clause by
+//! clause through the API), removes subsumed clauses, optionally
+//! eliminates variables and hands back the simplified clause list together
+//! with statistics.
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! message {
+    ($verbosity:expr, $($arg:tt)*) => {{
+        use std::io::{self, Write};
+        if $verbosity >= 0 {
+            let stdout = io::stdout();
+            let mut handle = stdout.lock();
+            // messages are best effort and must not abort the caller
+            let _ = writeln!(handle, "{}", format!("c {}", format_args!($($arg)*)));
+            let _ = handle.flush();
+        }
+    }}
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! verbose {
+    ($verbosity:expr, $level:expr, $($arg:tt)*) => {{
+        use std::io::{self, Write};
+        if $verbosity >= $level {
+            let stdout = io::stdout();
+            let mut handle = stdout.lock();
+            // messages are best effort and must not abort the caller
+            let _ = writeln!(handle, "{}", format!("c {}", format_args!($($arg)*)));
+            let _ = handle.flush();
+        }
+    }}
+}
+
+macro_rules! parse_error {
+    ($ctx:expr, $msg:expr, $line:expr, $column:expr) => {
+        $crate::error::BabysubError::Parse {
+            path: $ctx.config.input_path.clone(),
+            line: $line,
+            column: $column,
+            message: $msg.to_string(),
+        }
+    };
+}
+
+#[cfg(feature = "logging")]
+macro_rules! LOG {
+    ($verbosity:expr, $($arg:tt)*) => {{
+        use std::io::{self, Write};
+        if $verbosity >= 999 {
+            let stdout = io::stdout();
+            let mut handle = stdout.lock();
+            let _ = writeln!(handle, "{}", format!("c LOG {}", format_args!($($arg)*)));
+            let _ = handle.flush();
+        }
+    }}
+}
+
+#[cfg(not(feature = "logging"))]
+macro_rules! LOG {
+    ($($arg:tt)*) => {{}};
+}
+
+mod block;
+mod compact;
+mod config;
+mod deduplicate;
+mod eliminate;
+mod error;
+mod extend;
+mod formula;
+mod parse;
+mod preprocessor;
+mod print;
+mod probe;
+mod proof;
+mod propagate;
+mod pure;
+mod simplify;
+mod stats;
+mod substitute;
+mod subsume;
+
+pub use compact::Mapping;
+pub use config::Config;
+pub use error::{BabysubError, Result};
+pub use extend::{read_solution, Reconstruction};
+pub use formula::CNFFormula as Formula;
+pub use parse::input_compression;
+pub use preprocessor::Preprocessor;
+pub use stats::Stats;
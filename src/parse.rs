@@ -0,0 +1,596 @@
+use crate::error::{BabysubError, Result};
+use crate::preprocessor::SATContext;
+use crate::proof::delete_clause;
+use bzip2::read::BzDecoder;
+use flate2::read::GzDecoder;
+use lz4_flex::frame::FrameDecoder;
+use memmap2::Mmap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+use xz2::read::XzDecoder;
+
+// Copies the literals straight into the arena, dropping duplicates, unless
+// the clause turns out to be a tautology.
+pub(crate) fn import_clause(ctx: &mut SATContext, original: &[i32]) {
+    LOG!(ctx.config.verbosity, "parsed clause: {:?}", original);
+    ctx.stats.parsed += 1;
+    let id = ctx.formula.next_id();
+    let formula = &mut ctx.formula;
+    let clause_id = formula.clauses.start(id);
+    let mut is_trivial = false;
+    for &lit in original {
+        if formula.marks.is_marked(lit) {
+            continue; // Skip duplicates
+        }
+        if formula.marks.is_marked(-lit) {
+            is_trivial = true;
+            break; // Found trivial condition
+        }
+        formula.marks.mark(lit);
+        formula.clauses.push_literal(lit);
+    }
+    for &lit in formula.clauses.pending(clause_id) {
+        formula.marks.unmark(lit);
+    }
+
+    let clause = formula.clauses.pending(clause_id);
+    if let Some(proof) = &mut ctx.proof {
+        if is_trivial {
+            proof.delete(id, original);
+        } else if clause.len() < original.len() && !proof.is_lrat() {
+            // LRAT refers to clauses by id, so the duplicates do not matter
+            proof.add(id, clause, &[]);
+            proof.delete(id, original);
+        }
+    }
+    if is_trivial {
+        formula.clauses.discard(clause_id);
+        verbose!(ctx.config.verbosity, 2, "skipping trivial clause");
+        return;
+    }
+    if clause.is_empty() {
+        formula.empty_clause_exists = true;
+        verbose!(ctx.config.verbosity, 2, "found empty clause");
+    }
+    // duplicated clauses are removed in bulk by 'deduplicate'
+    formula.finish_clause(clause_id, ctx.config.verbosity);
+}
+
+// Removes a clause with the given literals, in any order and possibly
+// duplicated, and returns whether one was found. Clauses the formula still
+// depends on, present or replaced by derived ones, are kept.
+pub(crate) fn remove_clause(ctx: &mut SATContext, mut literals: Vec<i32>) -> Result<bool> {
+    literals.sort_unstable();
+    literals.dedup();
+    let found = ctx.formula.find_clause(&literals);
+    let clauses = &ctx.formula.clauses;
+    let in_use = match found {
+        Some(clause_id) => {
+            clauses.is_used(clause_id) || clauses.has_live_derived(&[clauses.id(clause_id)])
+        }
+        None => clauses.has_live_derived(clauses.removed_antecedents(&literals)),
+    };
+    if in_use {
+        return Err(BabysubError::ClauseInUse(literals));
+    }
+    match found {
+        Some(clause_id) => {
+            LOG!(ctx.config.verbosity, "removing clause {:?}", literals);
+            delete_clause(ctx, clause_id);
+            if literals.is_empty() {
+                ctx.formula.empty_clause_exists = ctx.formula.find_clause(&[]).is_some();
+            }
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+fn column(line: &str, token: &str) -> usize {
+    token.as_ptr() as usize - line.as_ptr() as usize + 1
+}
+
+// Compression formats recognized by the magic bytes at the start of a
+// stream, together with the file name suffix they usually come with.
+const FORMATS: [(&str, &str, &[u8]); 5] = [
+    ("bzip2", "bz2", b"BZh"),
+    ("gzip", "gz", &[0x1f, 0x8b]),
+    ("xz", "xz", &[0xfd, b'7', b'z', b'X', b'Z', 0x00]),
+    ("zstd", "zst", &[0x28, 0xb5, 0x2f, 0xfd]),
+    ("lz4", "lz4", &[0x04, 0x22, 0x4d, 0x18]),
+];
+
+fn sniff_compression(magic: &[u8]) -> Option<&'static str> {
+    FORMATS
+        .iter()
+        .find(|(_, _, bytes)| magic.starts_with(bytes))
+        .map(|&(format, _, _)| format)
+}
+
+fn suffix_compression(input_path: &str) -> Option<&'static str> {
+    let extension = Path::new(input_path).extension()?.to_str()?;
+    FORMATS
+        .iter()
+        .find(|&&(_, suffix, _)| suffix == extension)
+        .map(|&(format, _, _)| format)
+}
+
+fn decoder(format: &'static str, input: Box<dyn Read>, _verbosity: i32) -> Result<Box<dyn Read>> {
+    LOG!(_verbosity, "reading {} compressed input", format);
+    match format {
+        "bzip2" => Ok(Box::new(BzDecoder::new(input))),
+        "gzip" => Ok(Box::new(GzDecoder::new(input))),
+        "xz" => Ok(Box::new(XzDecoder::new(input))),
+        "zstd" => Ok(Box::new(
+            zstd::Decoder::new(input).map_err(|e| BabysubError::stream(Some(format), e))?,
+        )),
+        _ => Ok(Box::new(FrameDecoder::new(input))),
+    }
+}
+
+// Sniffs the compression from the first bytes, which has to match the suffix
+// of files named like compressed files.
+fn detect_compression(input_path: &str, magic: &[u8]) -> Result<Option<&'static str>> {
+    let compression = sniff_compression(magic);
+    if input_path != "<stdin>" {
+        if let Some(expected) = suffix_compression(input_path) {
+            if compression != Some(expected) {
+                return Err(BabysubError::FormatMismatch {
+                    path: input_path.to_string(),
+                    expected,
+                    found: compression.unwrap_or("uncompressed"),
+                });
+            }
+        }
+    }
+    Ok(compression)
+}
+
+fn open(path: &str) -> Result<File> {
+    File::open(path).map_err(|e| BabysubError::open(path, e))
+}
+
+// Maps an uncompressed regular file into memory. Returns 'None' for
+// standard input, pipes, empty or compressed files and whenever mapping
+// fails, which are then read as a stream.
+fn map_input(input_path: &str) -> Result<Option<Mmap>> {
+    if input_path == "<stdin>" {
+        return Ok(None);
+    }
+    let file = open(input_path)?;
+    let metadata = file.metadata()?;
+    if !metadata.is_file() || metadata.len() == 0 {
+        return Ok(None);
+    }
+    // SAFETY: the mapping is only read while parsing. Another process
+    // truncating the file meanwhile would crash babysub, which is the usual
+    // trade-off of mapping input files.
+    let map = match unsafe { Mmap::map(&file) } {
+        Ok(map) => map,
+        Err(_) => return Ok(None),
+    };
+    if detect_compression(input_path, &map[..map.len().min(6)])?.is_some() {
+        return Ok(None);
+    }
+    #[cfg(unix)]
+    let _ = map.advise(memmap2::Advice::Sequential);
+    Ok(Some(map))
+}
+
+/// Compression format of an input file, detected from its first bytes like
+/// when parsing it, or `None` if it is not compressed.
+pub fn input_compression(input_path: &str) -> Result<Option<&'static str>> {
+    let mut magic = Vec::with_capacity(6);
+    open(input_path)?.take(6).read_to_end(&mut magic)?;
+    detect_compression(input_path, &magic)
+}
+
+// Opens a possibly compressed input, detecting the compression from its
+// first bytes, and returns the name of the compression format, if any, for
+// error reporting. Files named like compressed files must contain that
+// format.
+pub(crate) fn open_input(
+    input_path: &str,
+    verbosity: i32,
+) -> Result<(Box<dyn Read>, Option<&'static str>)> {
+    message!(verbosity, "reading from '{}'", input_path);
+    let mut input: Box<dyn Read> = if input_path == "<stdin>" {
+        Box::new(io::stdin())
+    } else {
+        Box::new(open(input_path)?)
+    };
+    let mut magic = Vec::with_capacity(6);
+    input.by_ref().take(6).read_to_end(&mut magic)?;
+    let compression = detect_compression(input_path, &magic)?;
+    // the sniffed bytes are put back in front of the rest
+    let input: Box<dyn Read> = Box::new(io::Cursor::new(magic).chain(input));
+    let input = match compression {
+        Some(format) => decoder(format, input, verbosity)?,
+        None => {
+            LOG!(verbosity, "reading uncompressed input");
+            input
+        }
+    };
+    Ok((input, compression))
+}
+
+// Reads the bytes of a DIMACS input one by one straight from the buffer of
+// 'input' and keeps track of their line and column.
+struct Scanner<R: BufRead> {
+    input: R,
+    compression: Option<&'static str>,
+    line: usize,
+    // Number of bytes consumed and the number consumed before this line.
+    offset: usize,
+    line_start: usize,
+}
+
+impl<R: BufRead> Scanner<R> {
+    fn new(input: R, compression: Option<&'static str>) -> Self {
+        Scanner {
+            input,
+            compression,
+            line: 1,
+            offset: 0,
+            line_start: 0,
+        }
+    }
+
+    fn column(&self) -> usize {
+        self.offset - self.line_start + 1
+    }
+
+    fn buffer(&mut self) -> Result<&[u8]> {
+        self.input
+            .fill_buf()
+            .map_err(|e| BabysubError::stream(self.compression, e))
+    }
+
+    fn peek(&mut self) -> Result<Option<u8>> {
+        Ok(self.buffer()?.first().copied())
+    }
+
+    fn advance(&mut self, byte: u8) {
+        self.input.consume(1);
+        self.offset += 1;
+        if byte == b'\n' {
+            self.line += 1;
+            self.line_start = self.offset;
+        }
+    }
+
+    // Skips white space including line breaks and returns the next byte,
+    // scanning the buffer a whole slice at a time.
+    fn skip_space(&mut self) -> Result<Option<u8>> {
+        loop {
+            let offset = self.offset;
+            let buffer = self.buffer()?;
+            if buffer.is_empty() {
+                return Ok(None);
+            }
+            let mut skipped = 0;
+            let mut line_start = None;
+            let mut lines = 0;
+            let mut next = None;
+            for &byte in buffer {
+                if !byte.is_ascii_whitespace() {
+                    next = Some(byte);
+                    break;
+                }
+                skipped += 1;
+                if byte == b'\n' {
+                    lines += 1;
+                    line_start = Some(offset + skipped);
+                }
+            }
+            self.input.consume(skipped);
+            self.offset += skipped;
+            self.line += lines;
+            if let Some(line_start) = line_start {
+                self.line_start = line_start;
+            }
+            if next.is_some() {
+                return Ok(next);
+            }
+        }
+    }
+
+    // Consumes the rest of the line including the line break.
+    fn read_line(&mut self) -> Result<Vec<u8>> {
+        let mut line = Vec::new();
+        while let Some(byte) = self.peek()? {
+            self.advance(byte);
+            if byte == b'\n' {
+                break;
+            }
+            line.push(byte);
+        }
+        Ok(line)
+    }
+
+    // Reads an integer token, with a leading '+' allowed unless 'strict', or
+    // returns 'None' if the token is not one, leaving the scanner after the
+    // bytes consumed so far.
+    fn read_integer(&mut self, strict: bool) -> Result<Option<i64>> {
+        let mut sign = 1;
+        match self.peek()? {
+            Some(b'-') => {
+                self.advance(b'-');
+                sign = -1;
+            }
+            Some(b'+') if !strict => self.advance(b'+'),
+            _ => {}
+        }
+        let mut value: i64 = 0;
+        let mut digits = 0;
+        loop {
+            let buffer = self.buffer()?;
+            let mut consumed = 0;
+            let mut end = None;
+            for &byte in buffer {
+                if !byte.is_ascii_digit() {
+                    end = Some(byte);
+                    break;
+                }
+                value = value * 10 + (byte - b'0') as i64;
+                consumed += 1;
+                if value > i32::MAX as i64 + 1 {
+                    end = Some(byte);
+                    break;
+                }
+            }
+            let exhausted = buffer.is_empty();
+            self.input.consume(consumed);
+            self.offset += consumed;
+            digits += consumed;
+            match end {
+                Some(byte) if !byte.is_ascii_whitespace() => return Ok(None),
+                Some(_) => break,
+                None if exhausted => break,
+                None => {}
+            }
+        }
+        Ok((digits > 0).then_some(sign * value))
+    }
+}
+
+// The parsed 'p cnf' header with the position of its clause count.
+struct Header {
+    line: usize,
+    column: usize,
+    variables: usize,
+    clauses: usize,
+}
+
+// Parses the header line starting at 'first_column'.
+fn parse_header(
+    ctx: &mut SATContext,
+    line: &str,
+    line_number: usize,
+    first_column: usize,
+) -> Result<Header> {
+    let column = |token: &str| column(line, token) + first_column - 1;
+    let parts: Vec<&str> = line.split_whitespace().collect();
+    if parts.len() < 4 || parts[0] != "p" || parts[1] != "cnf" {
+        return Err(parse_error!(
+            ctx,
+            "Invalid header format.",
+            line_number,
+            first_column
+        ));
+    }
+    let variables: usize = parts[2].parse().map_err(|_| {
+        parse_error!(
+            ctx,
+            "Could not read number of variables.",
+            line_number,
+            column(parts[2])
+        )
+    })?;
+    if variables > i32::MAX as usize {
+        return Err(BabysubError::ResourceLimit(format!(
+            "{} variables exceed the maximum of {}",
+            variables,
+            i32::MAX
+        )));
+    }
+    let clauses_count: usize = parts[3].parse().map_err(|_| {
+        parse_error!(
+            ctx,
+            "Could not read number of clauses.",
+            line_number,
+            column(parts[3])
+        )
+    })?;
+    if ctx.config.strict && parts.len() > 4 {
+        return Err(parse_error!(
+            ctx,
+            "Expected end of line after header.",
+            line_number,
+            column(parts[4])
+        ));
+    }
+    message!(
+        ctx.config.verbosity,
+        "parsed 'p cnf {} {}' header",
+        variables,
+        clauses_count
+    );
+    Ok(Header {
+        line: line_number,
+        column: column(parts[3]),
+        variables,
+        clauses: clauses_count,
+    })
+}
+
+// Literals beyond the declared variables are an error unless parsing is
+// relaxed, which grows the variable range instead.
+fn check_variable(ctx: &mut SATContext, lit: i32, line: usize, column: usize) -> Result<()> {
+    let var = lit.unsigned_abs() as usize;
+    if var > ctx.formula.variables {
+        if !ctx.config.relaxed || var > i32::MAX as usize {
+            return Err(BabysubError::HeaderMismatch {
+                path: ctx.config.input_path.clone(),
+                line,
+                column,
+                message: format!(
+                    "literal {} exceeds the {} declared variables",
+                    lit, ctx.formula.variables
+                ),
+            });
+        }
+        ctx.formula.resize(var, ctx.config.verbosity);
+    }
+    Ok(())
+}
+
+// Reads DIMACS as a stream of tokens, where comments and the header extend
+// to the end of their line and every zero terminates a clause, no matter
+// how clauses are spread over lines.
+fn parse_dimacs<R: BufRead>(
+    ctx: &mut SATContext,
+    input: R,
+    compression: Option<&'static str>,
+) -> Result<()> {
+    let mut scanner = Scanner::new(input, compression);
+    let strict = ctx.config.strict;
+    let mut header: Option<Header> = None;
+    let mut clause = Vec::new();
+    let mut clauses = 0;
+    // Position after the last literal, where a missing zero is reported.
+    let mut end = (1, 1);
+
+    while let Some(byte) = scanner.skip_space()? {
+        let line = scanner.line;
+        let column = scanner.column();
+        if byte == b'c' {
+            scanner.advance(byte);
+            let next = scanner.peek()?;
+            if strict && next.is_some_and(|next| !next.is_ascii_whitespace()) {
+                return Err(parse_error!(
+                    ctx,
+                    "Expected white space after comment marker 'c'.",
+                    line,
+                    column + 1
+                ));
+            }
+            scanner.read_line()?;
+            continue; // Skip comments
+        }
+        if byte == b'p' {
+            if let (true, Some(first)) = (strict, &header) {
+                return Err(parse_error!(
+                    ctx,
+                    format!(
+                        "Duplicated header, the first one is at line {}.",
+                        first.line
+                    ),
+                    line,
+                    column
+                ));
+            }
+            let text = scanner.read_line()?;
+            let text = String::from_utf8_lossy(&text);
+            let parsed = parse_header(ctx, &text, line, column)?;
+            match &header {
+                // clauses may already use the variables of the first header
+                Some(_) if parsed.variables > ctx.formula.variables => {
+                    ctx.formula.resize(parsed.variables, ctx.config.verbosity)
+                }
+                Some(_) => {}
+                None => {
+                    ctx.formula.init(parsed.variables, ctx.config.verbosity);
+                    header = Some(parsed);
+                }
+            }
+            continue;
+        }
+        let Some(declared) = header.as_ref().map(|header| header.clauses) else {
+            return Err(parse_error!(ctx, "CNF header not found.", line, column));
+        };
+        if strict && clauses == declared {
+            return Err(parse_error!(
+                ctx,
+                "Junk after the last clause.",
+                line,
+                column
+            ));
+        }
+        let lit = match scanner.read_integer(strict)? {
+            Some(lit) if lit.unsigned_abs() <= i32::MAX as u64 => lit as i32,
+            _ => return Err(parse_error!(ctx, "Invalid literal format.", line, column)),
+        };
+        if lit == 0 {
+            import_clause(ctx, &clause);
+            clause.clear();
+            clauses += 1;
+            continue;
+        }
+        check_variable(ctx, lit, line, column)?;
+        clause.push(lit);
+        end = (scanner.line, scanner.column());
+    }
+
+    if !clause.is_empty() {
+        if strict {
+            return Err(parse_error!(
+                ctx,
+                "Clause not terminated by zero.",
+                end.0,
+                end.1
+            ));
+        }
+        import_clause(ctx, &clause);
+        clauses += 1;
+    }
+    match header {
+        Some(header) if strict && clauses != header.clauses => {
+            return Err(BabysubError::HeaderMismatch {
+                path: ctx.config.input_path.clone(),
+                line: header.line,
+                column: header.column,
+                message: format!(
+                    "found {} clauses but the header declares {}",
+                    clauses, header.clauses
+                ),
+            });
+        }
+        None if strict => {
+            return Err(parse_error!(
+                ctx,
+                "CNF header not found.",
+                scanner.line,
+                scanner.column()
+            ));
+        }
+        _ => {}
+    }
+    verbose!(
+        ctx.config.verbosity,
+        1,
+        "parsed {} clauses",
+        ctx.stats.parsed
+    );
+    Ok(())
+}
+
+// Parses uncompressed files straight from a memory mapping if enabled and
+// everything else through a buffered, possibly decompressing stream.
+pub(crate) fn parse_cnf(input_path: String, ctx: &mut SATContext) -> Result<()> {
+    if ctx.config.mmap {
+        if let Some(map) = map_input(&input_path)? {
+            message!(ctx.config.verbosity, "reading from '{}'", input_path);
+            verbose!(
+                ctx.config.verbosity,
+                1,
+                "mapped {} bytes into memory",
+                map.len()
+            );
+            return parse_dimacs(ctx, &map[..], None);
+        }
+    }
+    let (input, compression) = open_input(&input_path, ctx.config.verbosity)?;
+    parse_dimacs(ctx, BufReader::with_capacity(1 << 16, input), compression)
+}
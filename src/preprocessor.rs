@@ -0,0 +1,212 @@
+use crate::compact::Mapping;
+use crate::config::Config;
+use crate::error::Result;
+use crate::extend::{extend_model, write_reconstruction};
+use crate::formula::CNFFormula;
+use crate::parse::{import_clause, parse_cnf, remove_clause};
+use crate::print::{compute_signature, print};
+use crate::proof::Proof;
+use crate::simplify::simplify;
+use crate::stats::{report_stats, Stats};
+use crate::subsume::{subsume, Mode};
+
+pub(crate) struct SATContext {
+    pub(crate) config: Config,
+    pub(crate) formula: CNFFormula,
+    pub(crate) stats: Stats,
+    pub(crate) proof: Option<Proof>,
+}
+
+impl SATContext {
+    fn new(config: Config) -> Self {
+        SATContext {
+            config,
+            formula: CNFFormula::new(),
+            stats: Stats::new(),
+            proof: None,
+        }
+    }
+}
+
+/// Subsumption preprocessor over a single CNF formula.
+///
+/// Clauses are loaded either from the DIMACS file named in the
+/// configuration or one by one with [`add_clause`](Self::add_clause),
+/// then [`simplify`](Self::simplify) removes subsumed clauses.
+pub struct Preprocessor {
+    ctx: SATContext,
+}
+
+impl Preprocessor {
+    pub fn new(config: Config) -> Self {
+        let mut ctx = SATContext::new(config);
+        for &var in &ctx.config.frozen {
+            ctx.formula.freeze(var);
+        }
+        Preprocessor { ctx }
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.ctx.config
+    }
+
+    pub fn formula(&self) -> &CNFFormula {
+        &self.ctx.formula
+    }
+
+    pub fn stats(&self) -> &Stats {
+        &self.ctx.stats
+    }
+
+    /// Starts the proof configured with [`Config::proof_path`].
+    ///
+    /// Called by [`parse`](Self::parse), so this is only needed before
+    /// adding clauses with [`add_clause`](Self::add_clause).
+    pub fn open_proof(&mut self) -> Result<()> {
+        if self.ctx.proof.is_none() {
+            if let Some(path) = &self.ctx.config.proof_path {
+                verbose!(self.ctx.config.verbosity, 1, "writing proof to '{}'", path);
+                self.ctx.proof = Some(Proof::open(
+                    path,
+                    self.ctx.config.binary_proof,
+                    self.ctx.config.lrat,
+                    self.ctx.config.compression_level,
+                )?);
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the end of the proof, which compressed proofs need, and
+    /// reports whether writing it failed. Nothing can be logged afterwards,
+    /// so this is called once simplification is done.
+    pub fn finish_proof(&mut self) -> Result<()> {
+        match &mut self.ctx.proof {
+            Some(proof) => proof.finish(),
+            None => Ok(()),
+        }
+    }
+
+    /// Reads the DIMACS formula from the configured input path.
+    pub fn parse(&mut self) -> Result<()> {
+        self.open_proof()?;
+        parse_cnf(self.ctx.config.input_path.clone(), &mut self.ctx)
+    }
+
+    /// Adds a clause given as non-zero DIMACS literals.
+    ///
+    /// The variable range grows as needed. Duplicated literals are removed
+    /// and tautological clauses are dropped, exactly as during parsing.
+    /// Clauses added after [`simplify`](Self::simplify) are the only ones
+    /// checked by the next call.
+    pub fn add_clause(&mut self, literals: &[i32]) {
+        assert!(!literals.contains(&0), "zero is not a literal");
+        let max_var = literals
+            .iter()
+            .map(|lit| lit.unsigned_abs() as usize)
+            .max()
+            .unwrap_or(0);
+        if max_var > self.ctx.formula.variables {
+            self.ctx.formula.resize(max_var, self.ctx.config.verbosity);
+        }
+        import_clause(&mut self.ctx, literals);
+    }
+
+    /// Protects the variable of `lit` from being eliminated or substituted,
+    /// so that it can still be used in clauses or assumptions added later.
+    /// Subsumption and strengthening still apply to its clauses.
+    pub fn freeze(&mut self, lit: i32) {
+        assert!(lit != 0, "zero is not a literal");
+        self.ctx.formula.freeze(lit.unsigned_abs() as usize);
+    }
+
+    /// Removes a clause with exactly the given literals, in any order, and
+    /// returns whether it was found. Fails with
+    /// [`BabysubError::ClauseInUse`](crate::BabysubError::ClauseInUse) if
+    /// other clauses were subsumed, strengthened or derived with it, as they
+    /// could not be restored, even if the clause itself has already been
+    /// replaced by a derived one.
+    pub fn remove_clause(&mut self, literals: &[i32]) -> Result<bool> {
+        remove_clause(&mut self.ctx, literals.to_vec())
+    }
+
+    /// Removes subsumed clauses, using forward, backward or combined
+    /// subsumption depending on [`Config::backward_mode`] and
+    /// [`Config::combined_mode`], followed by the optional passes enabled in
+    /// the configuration.
+    ///
+    /// Calling it again only runs subsumption, checking the clauses added
+    /// since forward and backward against the others. Clauses must not be
+    /// added over variables removed by the first call, which can be
+    /// prevented with [`freeze`](Self::freeze).
+    pub fn simplify(&mut self) -> Result<()> {
+        simplify(&mut self.ctx)
+    }
+
+    /// Runs forward subsumption regardless of the configured mode.
+    pub fn forward_subsumption(&mut self) {
+        subsume(&mut self.ctx, Mode::Forward);
+    }
+
+    /// Runs backward subsumption regardless of the configured mode.
+    pub fn backward_subsumption(&mut self) {
+        subsume(&mut self.ctx, Mode::Backward);
+    }
+
+    /// Runs combined subsumption regardless of the configured mode.
+    pub fn combined_subsumption(&mut self) {
+        subsume(&mut self.ctx, Mode::Combined);
+    }
+
+    /// Iterates over the literals of the current clauses.
+    pub fn clauses(&self) -> impl Iterator<Item = &[i32]> {
+        self.ctx.formula.clauses()
+    }
+
+    /// Extends a model of the simplified formula, given as DIMACS literals,
+    /// to a model of the original formula with one literal per variable.
+    pub fn extend(&self, model: &[i32]) -> Vec<i32> {
+        extend_model(&self.ctx, model)
+    }
+
+    /// Writes the extension stack needed by [`Reconstruction::extend`]
+    /// to `path`.
+    ///
+    /// [`Reconstruction::extend`]: crate::Reconstruction::extend
+    pub fn write_reconstruction(&self, path: &str) -> Result<()> {
+        write_reconstruction(&self.ctx, path)
+    }
+
+    /// Dense renumbering of the variables occurring in the current clauses
+    /// and of the frozen ones, as used for printing with
+    /// [`Config::compact`]. Models of the compacted formula have to be
+    /// translated with [`Mapping::translate`] before they are extended.
+    pub fn mapping(&self) -> Mapping {
+        Mapping::new(&self.ctx.formula)
+    }
+
+    /// Writes the mapping from compacted to original variables, needed by
+    /// [`Mapping::read`], to `path`.
+    pub fn write_mapping(&self, path: &str) -> Result<()> {
+        self.mapping().write(
+            path,
+            self.ctx.config.compression_level,
+            self.ctx.config.verbosity,
+        )
+    }
+
+    /// Hash signature of the current clauses, independent of their order.
+    pub fn signature(&self) -> u64 {
+        compute_signature(&self.ctx)
+    }
+
+    /// Writes the formula to the configured output path.
+    pub fn print(&self) -> Result<()> {
+        print(&self.ctx)
+    }
+
+    /// Prints the statistics as DIMACS comments.
+    pub fn report_stats(&self) {
+        report_stats(&self.ctx);
+    }
+}
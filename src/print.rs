@@ -0,0 +1,254 @@
+use crate::compact::Mapping;
+use crate::error::{BabysubError, Result};
+use crate::preprocessor::SATContext;
+use bzip2::write::BzEncoder;
+use flate2::write::GzEncoder;
+use lz4_flex::frame::FrameEncoder;
+use std::fs::File;
+use std::io::{self, Write};
+use xz2::write::XzEncoder;
+
+pub(crate) fn compute_signature(ctx: &SATContext) -> u64 {
+    signature_with(ctx, |lit| lit)
+}
+
+// Hash signature of the clauses with every literal replaced by 'rename'.
+fn signature_with(ctx: &SATContext, rename: impl Fn(i32) -> i32) -> u64 {
+    verbose!(ctx.config.verbosity, 1, "computing hash-signature");
+    let nonces = [
+        71876167, 708592741, 1483128881, 907283241, 442951013, 537146759, 1366999021, 1854614941,
+        647800535, 53523743, 783815875, 1643643143, 682599717, 291474505, 229233697, 1633529763,
+    ];
+    let mut hash: u64 = 0;
+
+    for clause in ctx.formula.clauses() {
+        let mut d: Vec<u32> = clause.iter().map(|&lit| rename(lit) as u32).collect();
+        d.sort_unstable();
+        let mut tmp = (d.len() as u64 + 1).wrapping_mul(nonces[0]);
+        let mut i = 1usize;
+
+        for &ulit in &d {
+            tmp = tmp.rotate_left(4);
+            tmp = tmp.wrapping_add(ulit as u64);
+            tmp = tmp.wrapping_mul(nonces[i]);
+            i = (i + 1) % nonces.len();
+        }
+
+        hash = hash.wrapping_add(tmp);
+    }
+
+    hash
+}
+
+// Checks a compression level against the range supported by 'format'.
+fn level(
+    format: &'static str,
+    level: Option<u32>,
+    default: u32,
+    range: std::ops::RangeInclusive<u32>,
+) -> Result<u32> {
+    match level {
+        None => Ok(default),
+        Some(level) if range.contains(&level) => Ok(level),
+        Some(level) => Err(BabysubError::Compression {
+            format,
+            source: io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "level {} is not between {} and {}",
+                    level,
+                    range.start(),
+                    range.end()
+                ),
+            ),
+        }),
+    }
+}
+
+enum Encoder {
+    Stdout(io::Stdout),
+    Plain(File),
+    Bzip2(BzEncoder<File>),
+    Gzip(GzEncoder<File>),
+    Xz(XzEncoder<File>),
+    Zstd(zstd::Encoder<'static, File>),
+    Lz4(FrameEncoder<File>),
+}
+
+impl Encoder {
+    fn get_mut(&mut self) -> &mut dyn Write {
+        match self {
+            Encoder::Stdout(output) => output,
+            Encoder::Plain(output) => output,
+            Encoder::Bzip2(output) => output,
+            Encoder::Gzip(output) => output,
+            Encoder::Xz(output) => output,
+            Encoder::Zstd(output) => output,
+            Encoder::Lz4(output) => output,
+        }
+    }
+
+    // Writes the end of a compressed stream and flushes it to the file.
+    fn finish(self) -> io::Result<()> {
+        match self {
+            Encoder::Stdout(mut output) => output.flush(),
+            Encoder::Plain(mut output) => output.flush(),
+            Encoder::Bzip2(output) => output.finish()?.flush(),
+            Encoder::Gzip(output) => output.finish()?.flush(),
+            Encoder::Xz(output) => output.finish()?.flush(),
+            Encoder::Zstd(output) => output.finish()?.flush(),
+            Encoder::Lz4(output) => output.finish()?.flush(),
+        }
+    }
+}
+
+/// A possibly compressed output, which has to be finished to write the end
+/// of a compressed stream and to learn whether that failed. Outputs dropped
+/// unfinished are still finished, ignoring errors.
+pub(crate) struct Output {
+    // 'None' once finished.
+    encoder: Option<Encoder>,
+}
+
+impl Output {
+    pub(crate) fn finish(&mut self) -> io::Result<()> {
+        match self.encoder.take() {
+            Some(encoder) => encoder.finish(),
+            None => Ok(()),
+        }
+    }
+
+    fn encoder(&mut self) -> io::Result<&mut dyn Write> {
+        match &mut self.encoder {
+            Some(encoder) => Ok(encoder.get_mut()),
+            None => Err(io::Error::other("output already finished")),
+        }
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.encoder()?.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.encoder()?.flush()
+    }
+}
+
+impl Drop for Output {
+    fn drop(&mut self) {
+        let _ = self.finish();
+    }
+}
+
+fn create(path: &str) -> Result<File> {
+    File::create(path).map_err(|e| BabysubError::create(path, e))
+}
+
+// Creates a possibly compressed output and returns the name of the
+// compression format, if any, for error reporting. The level applies to
+// every format except lz4, which has none and rejects it.
+pub(crate) fn open_output(
+    output_path: &str,
+    compression_level: Option<u32>,
+) -> Result<(Output, Option<&'static str>)> {
+    let mut compression = None;
+    let encoder = if output_path == "<stdout>" {
+        Encoder::Stdout(io::stdout())
+    } else {
+        match output_path {
+            path if path.ends_with(".bz2") => {
+                let level = level("bzip2", compression_level, 6, 1..=9)?;
+                let file = create(path)?;
+                compression = Some("bzip2");
+                Encoder::Bzip2(BzEncoder::new(file, bzip2::Compression::new(level)))
+            }
+            path if path.ends_with(".gz") => {
+                let level = level("gzip", compression_level, 6, 0..=9)?;
+                let file = create(path)?;
+                compression = Some("gzip");
+                Encoder::Gzip(GzEncoder::new(file, flate2::Compression::new(level)))
+            }
+            path if path.ends_with(".xz") => {
+                let level = level("xz", compression_level, 6, 0..=9)?;
+                let file = create(path)?;
+                compression = Some("xz");
+                Encoder::Xz(XzEncoder::new(file, level))
+            }
+            path if path.ends_with(".zst") => {
+                let level = level("zstd", compression_level, 3, 1..=22)?;
+                let file = create(path)?;
+                compression = Some("zstd");
+                let encoder = zstd::Encoder::new(file, level as i32)
+                    .map_err(|e| BabysubError::stream(compression, e))?;
+                Encoder::Zstd(encoder)
+            }
+            path if path.ends_with(".lz4") => {
+                if let Some(level) = compression_level {
+                    return Err(BabysubError::Compression {
+                        format: "lz4",
+                        source: io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!("level {} given but lz4 has no levels", level),
+                        ),
+                    });
+                }
+                let file = create(path)?;
+                compression = Some("lz4");
+                Encoder::Lz4(FrameEncoder::new(file))
+            }
+            path => Encoder::Plain(create(path)?),
+        }
+    };
+    let output = Output {
+        encoder: Some(encoder),
+    };
+    Ok((output, compression))
+}
+
+pub(crate) fn print(ctx: &SATContext) -> Result<()> {
+    let (mut output, compression) =
+        open_output(&ctx.config.output_path, ctx.config.compression_level)?;
+    let result = if ctx.config.compact {
+        let mapping = Mapping::new(&ctx.formula);
+        verbose!(
+            ctx.config.verbosity,
+            1,
+            "compacted {} to {} variables",
+            ctx.formula.variables,
+            mapping.len()
+        );
+        write_formula(ctx, &mut output, mapping.len(), |lit| mapping.compact(lit))
+    } else {
+        write_formula(ctx, &mut output, ctx.formula.variables, |lit| lit)
+    };
+    result
+        .and_then(|()| output.finish())
+        .map_err(|e| BabysubError::stream(compression, e))
+}
+
+fn write_formula(
+    ctx: &SATContext,
+    output: &mut dyn Write,
+    variables: usize,
+    rename: impl Fn(i32) -> i32 + Copy,
+) -> io::Result<()> {
+    writeln!(output, "p cnf {} {}", variables, ctx.formula.len())?;
+
+    if ctx.config.sign {
+        let signature = signature_with(ctx, rename);
+        writeln!(output, "c hash-signature: {}", signature)?;
+    }
+
+    for clause in ctx.formula.clauses() {
+        let literals = clause
+            .iter()
+            .map(|&lit| rename(lit).to_string())
+            .collect::<Vec<String>>()
+            .join(" ");
+        writeln!(output, "{} 0", literals)?;
+    }
+
+    output.flush()
+}
@@ -0,0 +1,250 @@
+use crate::formula::{CNFFormula, ClauseRef, Clauses};
+use crate::preprocessor::SATContext;
+use crate::propagate::propagate;
+
+// Literals implied by a probe together with the clauses which implied them,
+// starting with the probe itself, and the falsified clause if propagation
+// ran into a conflict.
+struct Implied {
+    trail: Vec<(i32, ClauseRef)>,
+    conflict: Option<ClauseRef>,
+}
+
+impl Implied {
+    // Clauses implying the trail up to 'end', in trail order.
+    fn reasons(&self, end: usize) -> impl Iterator<Item = ClauseRef> + '_ {
+        self.trail[1..end].iter().map(|&(_, reason)| reason)
+    }
+}
+
+// Ids of the clauses a unit is learned from, which are marked as used.
+fn hints(clauses: &mut Clauses, reasons: impl Iterator<Item = ClauseRef>) -> Vec<u64> {
+    reasons
+        .map(|clause_id| {
+            clauses.mark_used(clause_id);
+            clauses.id(clause_id)
+        })
+        .collect()
+}
+
+// Assigns 'lit' by marking it and propagates over the full occurrence
+// lists, counting the visited clauses in 'ticks'. Marks are cleared again
+// before returning.
+fn probe(formula: &mut CNFFormula, lit: i32, ticks: &mut usize, _verbosity: i32) -> Implied {
+    let mut implied = Implied {
+        trail: vec![(lit, 0)],
+        conflict: None,
+    };
+    formula.marks.mark(lit);
+    let mut next = 0;
+    'propagation: while next < implied.trail.len() {
+        let (propagated, _) = implied.trail[next];
+        next += 1;
+        for &clause_id in &formula.matrix[-propagated] {
+            if formula.clauses.is_garbage(clause_id) {
+                continue;
+            }
+            *ticks += 1;
+            let clause = &formula.clauses[clause_id];
+            let mut unassigned = 0;
+            let mut unit = 0;
+            let mut satisfied = false;
+            for &other in clause {
+                if formula.marks.is_marked(other) {
+                    satisfied = true;
+                    break;
+                }
+                if !formula.marks.is_marked(-other) {
+                    unassigned += 1;
+                    unit = other;
+                }
+            }
+            if satisfied || unassigned > 1 {
+                continue;
+            }
+            if unassigned == 0 {
+                LOG!(_verbosity, "probing {} falsifies {:?}", lit, clause);
+                implied.conflict = Some(clause_id);
+                break 'propagation;
+            }
+            formula.marks.mark(unit);
+            implied.trail.push((unit, clause_id));
+        }
+    }
+    for &(lit, _) in &implied.trail {
+        formula.marks.unmark(lit);
+    }
+    implied
+}
+
+// Adds a unit clause derived by probing, with the given antecedents.
+fn learn_unit(ctx: &mut SATContext, lit: i32, hints: &[u64]) {
+    let id = ctx.formula.next_id();
+    ctx.formula.clauses.derive(id, hints);
+    if let Some(proof) = &mut ctx.proof {
+        proof.add(id, &[lit], hints);
+    }
+    let clause_id = ctx.formula.add_clause(id, &[lit], ctx.config.verbosity);
+    ctx.formula.connect_clause(clause_id, ctx.config.verbosity);
+}
+
+// Learns '-lit' after probing 'lit' ran into a conflict.
+fn learn_failed(ctx: &mut SATContext, lit: i32, implied: &Implied) {
+    LOG!(ctx.config.verbosity, "failed literal {}", lit);
+    let conflict = implied.conflict.unwrap();
+    let reasons = implied
+        .reasons(implied.trail.len())
+        .chain(std::iter::once(conflict));
+    let hints = hints(&mut ctx.formula.clauses, reasons);
+    learn_unit(ctx, -lit, &hints);
+    ctx.stats.failed += 1;
+}
+
+// Learns the literals implied by both 'lit' and '-lit'. For the proof each
+// one is derived from two temporary binary clauses, one per polarity.
+fn lift(
+    ctx: &mut SATContext,
+    lit: i32,
+    positive: &Implied,
+    negative: &Implied,
+    learned: &[bool],
+) -> Vec<i32> {
+    for &(other, _) in &positive.trail {
+        ctx.formula.marks.mark(other);
+    }
+    let both: Vec<(usize, i32)> = negative.trail[1..]
+        .iter()
+        .enumerate()
+        .filter(|&(_, &(other, _))| {
+            ctx.formula.marks.is_marked(other) && !learned[other.unsigned_abs() as usize]
+        })
+        .map(|(index, &(other, _))| (index + 1, other))
+        .collect();
+    for &(other, _) in &positive.trail {
+        ctx.formula.marks.unmark(other);
+    }
+
+    let mut lifted = Vec::new();
+    for (negative_end, other) in both {
+        LOG!(
+            ctx.config.verbosity,
+            "lifting {} implied by both {} and {}",
+            other,
+            lit,
+            -lit
+        );
+        let positive_end = positive
+            .trail
+            .iter()
+            .position(|&(implied, _)| implied == other)
+            .unwrap();
+        let first = ctx.formula.next_id();
+        let second = ctx.formula.next_id();
+        let clauses = &mut ctx.formula.clauses;
+        let positive_hints = hints(clauses, positive.reasons(positive_end + 1));
+        let negative_hints = hints(clauses, negative.reasons(negative_end + 1));
+        clauses.derive(first, &positive_hints);
+        clauses.derive(second, &negative_hints);
+        if let Some(proof) = &mut ctx.proof {
+            proof.add(first, &[-lit, other], &positive_hints);
+            proof.add(second, &[lit, other], &negative_hints);
+        }
+        learn_unit(ctx, other, &[first, second]);
+        if let Some(proof) = &mut ctx.proof {
+            proof.delete(first, &[-lit, other]);
+            proof.delete(second, &[lit, other]);
+        }
+        ctx.stats.lifted += 1;
+        lifted.push(other);
+    }
+    lifted
+}
+
+// Probes both literals of every active variable once, unless the ticks
+// reach 'limit', and returns the number of units learned.
+fn probe_round(ctx: &mut SATContext, limit: usize) -> usize {
+    ctx.formula.connect_all_clauses(ctx.config.verbosity);
+    // units learned in this round are only assigned by the next propagation
+    let mut learned = vec![false; ctx.formula.variables + 1];
+    let mut units = 0;
+    for var in 1..=ctx.formula.variables {
+        if ctx.stats.probe_ticks >= limit {
+            break;
+        }
+        if learned[var] || ctx.formula.eliminated[var] || ctx.formula.values[var] != 0 {
+            continue;
+        }
+        let lit = var as i32;
+        let ticks = &mut ctx.stats.probe_ticks;
+        ctx.stats.probed += 1;
+        let positive = probe(&mut ctx.formula, lit, ticks, ctx.config.verbosity);
+        if positive.conflict.is_some() {
+            learn_failed(ctx, lit, &positive);
+            learned[var] = true;
+            units += 1;
+            continue;
+        }
+        ctx.stats.probed += 1;
+        let ticks = &mut ctx.stats.probe_ticks;
+        let negative = probe(&mut ctx.formula, -lit, ticks, ctx.config.verbosity);
+        if negative.conflict.is_some() {
+            learn_failed(ctx, -lit, &negative);
+            learned[var] = true;
+            units += 1;
+            continue;
+        }
+        for other in lift(ctx, lit, &positive, &negative, &learned) {
+            learned[other.unsigned_abs() as usize] = true;
+            units += 1;
+        }
+    }
+    units
+}
+
+/// Failed literal probing: assigns every literal in turn, learns its
+/// negation if unit propagation then falsifies a clause, and learns the
+/// literals implied by both polarities of a variable. Learned units are
+/// propagated at the root level before the next round. The clauses visited
+/// are bounded by [`Config::probe_effort`] times the literals in the
+/// formula.
+///
+/// [`Config::probe_effort`]: crate::Config::probe_effort
+pub(crate) fn probe_literals(ctx: &mut SATContext) {
+    verbose!(ctx.config.verbosity, 1, "starting failed literal probing");
+    let literals: usize = ctx.formula.clauses().map(<[i32]>::len).sum();
+    let limit = ctx
+        .stats
+        .probe_ticks
+        .saturating_add(ctx.config.probe_effort.saturating_mul(literals));
+    let mut round = 0;
+    loop {
+        // probing relies on root-level assignments being propagated
+        propagate(ctx);
+        if ctx.formula.empty_clause_exists {
+            break;
+        }
+        if ctx.stats.probe_ticks >= limit {
+            verbose!(ctx.config.verbosity, 2, "probing budget exhausted");
+            break;
+        }
+        round += 1;
+        let units = probe_round(ctx, limit);
+        verbose!(
+            ctx.config.verbosity,
+            2,
+            "probing round {} learned {} units",
+            round,
+            units
+        );
+        if units == 0 {
+            break;
+        }
+    }
+    verbose!(
+        ctx.config.verbosity,
+        1,
+        "found {} failed literals and lifted {} units",
+        ctx.stats.failed,
+        ctx.stats.lifted
+    );
+}
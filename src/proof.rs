@@ -0,0 +1,171 @@
+use crate::error::{BabysubError, Result};
+use crate::formula::ClauseRef;
+use crate::preprocessor::SATContext;
+use crate::print::{open_output, Output};
+use std::fmt::Display;
+use std::io::{self, BufWriter, Write};
+
+/// Writes clause additions and deletions either as DRAT or as LRAT, where
+/// every added clause lists the ids of the clauses it is derived from, in
+/// text or in the binary encoding understood by drat-trim and cake_lpr.
+pub(crate) struct Proof {
+    output: BufWriter<Output>,
+    compression: Option<&'static str>,
+    binary: bool,
+    lrat: bool,
+    // LRAT deletion lines are prefixed by the id of the latest clause.
+    last_id: u64,
+    // The first write error, reported when the proof is flushed.
+    error: Option<io::Error>,
+    pub(crate) added: usize,
+    pub(crate) deleted: usize,
+}
+
+impl Proof {
+    pub(crate) fn open(path: &str, binary: bool, lrat: bool, level: Option<u32>) -> Result<Self> {
+        let (output, compression) = open_output(path, level)?;
+        Ok(Proof {
+            output: BufWriter::new(output),
+            compression,
+            binary,
+            lrat,
+            last_id: 0,
+            error: None,
+            added: 0,
+            deleted: 0,
+        })
+    }
+
+    pub(crate) fn is_lrat(&self) -> bool {
+        self.lrat
+    }
+
+    fn write_binary(&mut self, mut value: u64) -> io::Result<()> {
+        while value > 127 {
+            self.output.write_all(&[(value & 127) as u8 | 128])?;
+            value >>= 7;
+        }
+        self.output.write_all(&[value as u8])
+    }
+
+    // Ids are encoded like positive literals, followed by a zero.
+    fn write_binary_ids(&mut self, ids: &[u64]) -> io::Result<()> {
+        for &id in ids {
+            self.write_binary(2 * id)?;
+        }
+        self.write_binary(0)
+    }
+
+    fn write_binary_lits(&mut self, clause: &[i32]) -> io::Result<()> {
+        for &lit in clause {
+            self.write_binary(2 * lit.unsigned_abs() as u64 + (lit < 0) as u64)?;
+        }
+        self.write_binary(0)
+    }
+
+    fn write_text_list<T: Display>(&mut self, items: &[T]) -> io::Result<()> {
+        for item in items {
+            write!(self.output, "{} ", item)?;
+        }
+        self.output.write_all(b"0")
+    }
+
+    fn write_addition(&mut self, id: u64, clause: &[i32], hints: &[u64]) -> io::Result<()> {
+        match (self.binary, self.lrat) {
+            (true, lrat) => {
+                self.output.write_all(b"a")?;
+                if lrat {
+                    self.write_binary(2 * id)?;
+                }
+                self.write_binary_lits(clause)?;
+                if lrat {
+                    self.write_binary_ids(hints)?;
+                }
+                Ok(())
+            }
+            (false, lrat) => {
+                if lrat {
+                    write!(self.output, "{} ", id)?;
+                }
+                self.write_text_list(clause)?;
+                if lrat {
+                    self.output.write_all(b" ")?;
+                    self.write_text_list(hints)?;
+                }
+                self.output.write_all(b"\n")
+            }
+        }
+    }
+
+    fn write_deletion(&mut self, id: u64, clause: &[i32]) -> io::Result<()> {
+        match (self.binary, self.lrat) {
+            (true, true) => {
+                self.output.write_all(b"d")?;
+                self.write_binary_ids(&[id])
+            }
+            (true, false) => {
+                self.output.write_all(b"d")?;
+                self.write_binary_lits(clause)
+            }
+            (false, true) => {
+                write!(self.output, "{} d ", self.last_id)?;
+                self.write_text_list(&[id])?;
+                self.output.write_all(b"\n")
+            }
+            (false, false) => {
+                self.output.write_all(b"d ")?;
+                self.write_text_list(clause)?;
+                self.output.write_all(b"\n")
+            }
+        }
+    }
+
+    /// Adds the clause with the given id, derived by unit propagation over
+    /// the clauses in 'hints' in that order (only used for LRAT).
+    pub(crate) fn add(&mut self, id: u64, clause: &[i32], hints: &[u64]) {
+        self.added += 1;
+        self.last_id = self.last_id.max(id);
+        if self.error.is_none() {
+            if let Err(e) = self.write_addition(id, clause, hints) {
+                self.error = Some(e);
+            }
+        }
+    }
+
+    pub(crate) fn delete(&mut self, id: u64, clause: &[i32]) {
+        self.deleted += 1;
+        self.last_id = self.last_id.max(id);
+        if self.error.is_none() {
+            if let Err(e) = self.write_deletion(id, clause) {
+                self.error = Some(e);
+            }
+        }
+    }
+
+    pub(crate) fn flush(&mut self) -> Result<()> {
+        let result = match self.error.take() {
+            Some(e) => Err(e),
+            None => self.output.flush(),
+        };
+        result.map_err(|e| BabysubError::stream(self.compression, e))
+    }
+
+    // Flushes the proof and ends a compressed one, after which writing
+    // fails.
+    pub(crate) fn finish(&mut self) -> Result<()> {
+        self.flush()?;
+        self.output
+            .get_mut()
+            .finish()
+            .map_err(|e| BabysubError::stream(self.compression, e))
+    }
+}
+
+// Marks a clause as garbage and records its deletion in the proof.
+pub(crate) fn delete_clause(ctx: &mut SATContext, clause_id: ClauseRef) {
+    let clauses = &mut ctx.formula.clauses;
+    clauses.mark_garbage(clause_id);
+    if let Some(proof) = &mut ctx.proof {
+        proof.delete(clauses.id(clause_id), &clauses[clause_id]);
+    }
+}
@@ -0,0 +1,140 @@
+use crate::formula::ClauseRef;
+use crate::preprocessor::SATContext;
+use crate::proof::delete_clause;
+
+// Adds the empty clause derived from a unit clause and a clause falsified
+// by the assignment it implies.
+fn conflict(ctx: &mut SATContext, lit: i32, clause_id: ClauseRef) {
+    verbose!(ctx.config.verbosity, 2, "root-level conflict on {}", lit);
+    let id = ctx.formula.next_id();
+    let hints = [
+        ctx.formula.reasons[lit.unsigned_abs() as usize],
+        ctx.formula.clauses.id(clause_id),
+    ];
+    ctx.formula.clauses.mark_used(clause_id);
+    ctx.formula.clauses.derive(id, &hints);
+    if let Some(proof) = &mut ctx.proof {
+        proof.add(id, &[], &hints);
+    }
+    ctx.formula.add_clause(id, &[], ctx.config.verbosity);
+    ctx.formula.empty_clause_exists = true;
+}
+
+fn is_reason(ctx: &SATContext, clause_id: ClauseRef, lit: i32) -> bool {
+    ctx.formula.reasons[lit.unsigned_abs() as usize] == ctx.formula.clauses.id(clause_id)
+}
+
+// Handles the unit clause 'clause_id' containing only 'lit'.
+fn assign(ctx: &mut SATContext, lit: i32, clause_id: ClauseRef) {
+    if is_reason(ctx, clause_id, lit) {
+        return;
+    }
+    match ctx.formula.value(lit) {
+        0 => {
+            LOG!(ctx.config.verbosity, "assigning {}", lit);
+            let var = lit.unsigned_abs() as usize;
+            ctx.formula.values[var] = if lit > 0 { 1 } else { -1 };
+            ctx.formula.reasons[var] = ctx.formula.clauses.id(clause_id);
+            ctx.formula.clauses.mark_used(clause_id);
+            ctx.formula.trail.push(lit);
+            ctx.stats.units += 1;
+        }
+        1 => delete_clause(ctx, clause_id),
+        _ => conflict(ctx, -lit, clause_id),
+    }
+}
+
+// Removes the false literal '-lit' from the clause, which is implied by the
+// clause and the unit clause of 'lit'.
+fn remove_false_literal(ctx: &mut SATContext, clause_id: ClauseRef, lit: i32) {
+    let id = ctx.formula.next_id();
+    let reason_id = ctx.formula.reasons[lit.unsigned_abs() as usize];
+    let clauses = &mut ctx.formula.clauses;
+    LOG!(
+        ctx.config.verbosity,
+        "removing false literal {} from {:?}",
+        -lit,
+        &clauses[clause_id]
+    );
+    let original = clauses[clause_id].to_vec();
+    let original_id = clauses.id(clause_id);
+    let literals: Vec<i32> = original
+        .iter()
+        .copied()
+        .filter(|&other| other != -lit)
+        .collect();
+    clauses.replace(clause_id, id, &literals, &[reason_id, original_id]);
+    if let Some(proof) = &mut ctx.proof {
+        proof.add(id, &literals, &[reason_id, original_id]);
+        proof.delete(original_id, &original);
+    }
+}
+
+fn propagate_literal(ctx: &mut SATContext, lit: i32) {
+    for clause_id in ctx.formula.matrix[lit].clone() {
+        if !ctx.formula.clauses.is_garbage(clause_id) && !is_reason(ctx, clause_id, lit) {
+            LOG!(
+                ctx.config.verbosity,
+                "removing satisfied clause {:?}",
+                &ctx.formula.clauses[clause_id]
+            );
+            delete_clause(ctx, clause_id);
+        }
+    }
+    for clause_id in ctx.formula.matrix[-lit].clone() {
+        if ctx.formula.clauses.is_garbage(clause_id) {
+            continue;
+        }
+        remove_false_literal(ctx, clause_id, lit);
+        // all other false literals are stripped before the clause is empty
+        let literals = &ctx.formula.clauses[clause_id];
+        if literals.len() == 1 {
+            let unit = literals[0];
+            assign(ctx, unit, clause_id);
+            if ctx.formula.empty_clause_exists {
+                return;
+            }
+        }
+    }
+}
+
+/// Root-level unit propagation: assigns the literals of unit clauses,
+/// removes satisfied clauses and strips false literals. Unit clauses are
+/// kept, as they still constrain the formula.
+pub(crate) fn propagate(ctx: &mut SATContext) {
+    if ctx.formula.empty_clause_exists {
+        return;
+    }
+    verbose!(ctx.config.verbosity, 1, "starting unit propagation");
+    ctx.formula.connect_all_clauses(ctx.config.verbosity);
+
+    for clause_id in ctx.formula.clauses.refs().to_vec() {
+        let clause = &ctx.formula.clauses[clause_id];
+        if !ctx.formula.clauses.is_garbage(clause_id) && clause.len() == 1 {
+            let unit = clause[0];
+            assign(ctx, unit, clause_id);
+            if ctx.formula.empty_clause_exists {
+                return;
+            }
+        }
+    }
+
+    // clauses added since, like the units learned by probing, contain no
+    // literals assigned earlier, so propagation resumes after those
+    while ctx.formula.propagated < ctx.formula.trail.len() {
+        let lit = ctx.formula.trail[ctx.formula.propagated];
+        ctx.formula.propagated += 1;
+        ctx.stats.propagated += 1;
+        propagate_literal(ctx, lit);
+        if ctx.formula.empty_clause_exists {
+            return;
+        }
+    }
+    verbose!(
+        ctx.config.verbosity,
+        1,
+        "propagated {} units",
+        ctx.formula.trail.len()
+    );
+    ctx.formula.collect_garbage_clauses(ctx.config.verbosity);
+}
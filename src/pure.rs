@@ -0,0 +1,53 @@
+use crate::extend::push_witness;
+use crate::preprocessor::SATContext;
+use crate::proof::delete_clause;
+
+fn occurs(ctx: &mut SATContext, lit: i32) -> bool {
+    ctx.formula.flush_garbage_occurrences(lit);
+    !ctx.formula.matrix[lit].is_empty()
+}
+
+/// Pure literal elimination: removes all clauses containing a literal whose
+/// negation does not occur. Removing them can make further literals pure,
+/// which are checked until a fixpoint is reached.
+pub(crate) fn eliminate_pure(ctx: &mut SATContext) {
+    verbose!(ctx.config.verbosity, 1, "starting pure literal elimination");
+    ctx.formula.connect_all_clauses(ctx.config.verbosity);
+    let before = ctx.stats.pure;
+    let mut schedule: Vec<i32> = (1..=ctx.formula.variables as i32)
+        .filter(|&var| !ctx.formula.eliminated[var as usize])
+        .flat_map(|var| [var, -var])
+        .collect();
+    while let Some(lit) = schedule.pop() {
+        let var = lit.unsigned_abs() as usize;
+        if ctx.formula.eliminated[var]
+            || ctx.formula.is_frozen(var)
+            || occurs(ctx, -lit)
+            || !occurs(ctx, lit)
+        {
+            continue;
+        }
+        LOG!(ctx.config.verbosity, "pure literal {}", lit);
+        ctx.formula.eliminated[var] = true;
+        ctx.stats.pure += 1;
+        for clause_id in ctx.formula.matrix[lit].clone() {
+            let clause = ctx.formula.clauses[clause_id].to_vec();
+            push_witness(ctx, lit, clause.clone());
+            delete_clause(ctx, clause_id);
+            // the negations of the other literals may have become pure
+            schedule.extend(
+                clause
+                    .iter()
+                    .filter(|&&other| other != lit)
+                    .map(|&other| -other),
+            );
+        }
+    }
+    verbose!(
+        ctx.config.verbosity,
+        1,
+        "found {} pure literals",
+        ctx.stats.pure - before
+    );
+    ctx.formula.collect_garbage_clauses(ctx.config.verbosity);
+}
@@ -0,0 +1,70 @@
+use crate::block::eliminate_blocked;
+use crate::deduplicate::deduplicate;
+use crate::eliminate::eliminate;
+use crate::error::Result;
+use crate::preprocessor::SATContext;
+use crate::probe::probe_literals;
+use crate::propagate::propagate;
+use crate::pure::eliminate_pure;
+use crate::substitute::substitute;
+use crate::subsume::{subsume, subsume_incrementally, Mode};
+
+pub(crate) fn simplify(ctx: &mut SATContext) -> Result<()> {
+    if ctx.formula.simplified {
+        subsume_incrementally(ctx);
+    } else {
+        simplify_all(ctx);
+        ctx.formula.clauses.clear_all_dirty();
+        ctx.formula.simplified = true;
+    }
+    if let Some(proof) = &mut ctx.proof {
+        proof.flush()?;
+        verbose!(
+            ctx.config.verbosity,
+            1,
+            "proof contains {} added and {} deleted clauses",
+            proof.added,
+            proof.deleted
+        );
+    }
+    Ok(())
+}
+
+fn simplify_all(ctx: &mut SATContext) {
+    // passes rebuild the occurrence lists themselves
+    ctx.formula.connected = false;
+    if !ctx.formula.empty_clause_exists {
+        deduplicate(ctx);
+    }
+    if ctx.config.propagate {
+        propagate(ctx);
+    }
+    if ctx.config.probe && !ctx.formula.empty_clause_exists {
+        probe_literals(ctx);
+    }
+    if ctx.config.substitute && !ctx.formula.empty_clause_exists {
+        substitute(ctx);
+    }
+    let mode = if ctx.config.combined_mode {
+        Mode::Combined
+    } else if ctx.config.backward_mode {
+        Mode::Backward
+    } else {
+        Mode::Forward
+    };
+    subsume(ctx, mode);
+    if ctx.config.pure && !ctx.formula.empty_clause_exists {
+        eliminate_pure(ctx);
+    }
+    if ctx.config.block && !ctx.formula.empty_clause_exists {
+        eliminate_blocked(ctx);
+    }
+    if ctx.config.eliminate && !ctx.formula.empty_clause_exists {
+        let eliminated = ctx.stats.eliminated;
+        eliminate(ctx);
+        if ctx.stats.eliminated > eliminated || ctx.formula.empty_clause_exists {
+            // resolvents are only backward subsumed during elimination
+            subsume(ctx, Mode::Forward);
+        }
+    }
+}
@@ -0,0 +1,256 @@
+use crate::preprocessor::SATContext;
+use std::time::Instant;
+
+fn average(a: usize, b: usize) -> f64 {
+    if b != 0 {
+        a as f64 / b as f64
+    } else {
+        0.0
+    }
+}
+
+fn percent(a: usize, b: usize) -> f64 {
+    100.0 * average(a, b)
+}
+
+/// Counters collected while parsing and simplifying.
+#[derive(Debug, Clone)]
+pub struct Stats {
+    /// Number of subsumption candidates checked.
+    pub checked: usize,
+    /// Number of checked candidates ruled out by their signature alone.
+    pub filtered: usize,
+    /// Number of clauses read or added.
+    pub parsed: usize,
+    /// Number of clauses removed because they were subsumed.
+    pub subsumed: usize,
+    /// Number of clauses removed because an equal clause came first.
+    pub duplicated: usize,
+    /// Number of literals removed by self-subsuming resolution.
+    pub strengthened: usize,
+    /// Number of rounds of combined subsumption.
+    pub rounds: usize,
+    /// Clauses subsumed and literals strengthened in each round of combined
+    /// subsumption.
+    pub round_counts: Vec<(usize, usize)>,
+    /// Number of pure literals whose clauses were removed.
+    pub pure: usize,
+    /// Number of clauses removed because they were blocked.
+    pub blocked: usize,
+    /// Number of resolvents checked for tautology while looking for blocked
+    /// clauses.
+    pub block_checks: usize,
+    /// Number of variables removed by bounded variable elimination.
+    pub eliminated: usize,
+    /// Number of resolvents added while eliminating variables.
+    pub resolvents: usize,
+    /// Number of variables assigned by root-level unit clauses.
+    pub units: usize,
+    /// Number of assigned literals propagated over the formula.
+    pub propagated: usize,
+    /// Number of literals assigned by failed literal probing.
+    pub probed: usize,
+    /// Number of clauses visited while propagating probes.
+    pub probe_ticks: usize,
+    /// Number of probed literals whose propagation failed.
+    pub failed: usize,
+    /// Number of units implied by both polarities of a probed variable.
+    pub lifted: usize,
+    /// Number of variables replaced by an equivalent literal.
+    pub substituted: usize,
+    pub(crate) start_time: Instant,
+}
+
+impl Stats {
+    pub(crate) fn new() -> Self {
+        Stats {
+            checked: 0,
+            filtered: 0,
+            parsed: 0,
+            subsumed: 0,
+            duplicated: 0,
+            strengthened: 0,
+            rounds: 0,
+            round_counts: Vec::new(),
+            pure: 0,
+            blocked: 0,
+            block_checks: 0,
+            eliminated: 0,
+            resolvents: 0,
+            units: 0,
+            propagated: 0,
+            probed: 0,
+            probe_ticks: 0,
+            failed: 0,
+            lifted: 0,
+            substituted: 0,
+            start_time: Instant::now(),
+        }
+    }
+
+    /// Seconds since the preprocessor was created.
+    pub fn process_time(&self) -> f64 {
+        self.start_time.elapsed().as_secs_f64()
+    }
+}
+
+pub(crate) fn report_stats(ctx: &SATContext) {
+    let elapsed_time = ctx.stats.process_time();
+    message!(
+        ctx.config.verbosity,
+        "{:<20} {:>10}    clauses {:.2} per subsumed",
+        "checked:",
+        ctx.stats.checked,
+        average(ctx.stats.subsumed, ctx.stats.subsumed)
+    );
+    message!(
+        ctx.config.verbosity,
+        "{:<20} {:>10}    checks {:.0}%",
+        "filtered:",
+        ctx.stats.filtered,
+        percent(ctx.stats.filtered, ctx.stats.checked)
+    );
+    message!(
+        ctx.config.verbosity,
+        "{:<20} {:>10}    clauses {:.0}%",
+        "duplicated:",
+        ctx.stats.duplicated,
+        percent(ctx.stats.duplicated, ctx.stats.parsed)
+    );
+    message!(
+        ctx.config.verbosity,
+        "{:<20} {:>10}    clauses {:.0}%",
+        "subsumed:",
+        ctx.stats.subsumed,
+        percent(ctx.stats.subsumed, ctx.stats.parsed)
+    );
+    if ctx.config.combined_mode {
+        message!(
+            ctx.config.verbosity,
+            "{:<20} {:>10}    subsumed {:.2} per round",
+            "rounds:",
+            ctx.stats.rounds,
+            average(ctx.stats.subsumed, ctx.stats.rounds)
+        );
+        for (index, &(subsumed, strengthened)) in ctx.stats.round_counts.iter().enumerate() {
+            message!(
+                ctx.config.verbosity,
+                "{:<20} {:>10}    subsumed and {} strengthened",
+                format!("round-{}:", index + 1),
+                subsumed,
+                strengthened
+            );
+        }
+    }
+    if ctx.config.propagate || ctx.config.probe {
+        message!(
+            ctx.config.verbosity,
+            "{:<20} {:>10}    variables {:.0}%",
+            "units:",
+            ctx.stats.units,
+            percent(ctx.stats.units, ctx.formula.variables)
+        );
+        message!(
+            ctx.config.verbosity,
+            "{:<20} {:>10}    literals {:.0}%",
+            "propagated:",
+            ctx.stats.propagated,
+            percent(ctx.stats.propagated, ctx.stats.units)
+        );
+    }
+    if ctx.config.probe {
+        message!(
+            ctx.config.verbosity,
+            "{:<20} {:>10}    literals {:.2} per variable",
+            "probed:",
+            ctx.stats.probed,
+            average(ctx.stats.probed, ctx.formula.variables)
+        );
+        message!(
+            ctx.config.verbosity,
+            "{:<20} {:>10}    ticks {:.2} per probed",
+            "probe-ticks:",
+            ctx.stats.probe_ticks,
+            average(ctx.stats.probe_ticks, ctx.stats.probed)
+        );
+        message!(
+            ctx.config.verbosity,
+            "{:<20} {:>10}    literals {:.0}%",
+            "failed:",
+            ctx.stats.failed,
+            percent(ctx.stats.failed, ctx.stats.probed)
+        );
+        message!(
+            ctx.config.verbosity,
+            "{:<20} {:>10}    variables {:.0}%",
+            "lifted:",
+            ctx.stats.lifted,
+            percent(ctx.stats.lifted, ctx.formula.variables)
+        );
+    }
+    if ctx.config.substitute {
+        message!(
+            ctx.config.verbosity,
+            "{:<20} {:>10}    variables {:.0}%",
+            "substituted:",
+            ctx.stats.substituted,
+            percent(ctx.stats.substituted, ctx.formula.variables)
+        );
+    }
+    if ctx.config.strengthen {
+        message!(
+            ctx.config.verbosity,
+            "{:<20} {:>10}    literals {:.2} per clause",
+            "strengthened:",
+            ctx.stats.strengthened,
+            average(ctx.stats.strengthened, ctx.stats.parsed)
+        );
+    }
+    if ctx.config.pure {
+        message!(
+            ctx.config.verbosity,
+            "{:<20} {:>10}    variables {:.0}%",
+            "pure:",
+            ctx.stats.pure,
+            percent(ctx.stats.pure, ctx.formula.variables)
+        );
+    }
+    if ctx.config.block {
+        message!(
+            ctx.config.verbosity,
+            "{:<20} {:>10}    clauses {:.0}%",
+            "blocked:",
+            ctx.stats.blocked,
+            percent(ctx.stats.blocked, ctx.stats.parsed)
+        );
+        message!(
+            ctx.config.verbosity,
+            "{:<20} {:>10}    checks {:.2} per blocked",
+            "block-checks:",
+            ctx.stats.block_checks,
+            average(ctx.stats.block_checks, ctx.stats.blocked)
+        );
+    }
+    if ctx.config.eliminate {
+        message!(
+            ctx.config.verbosity,
+            "{:<20} {:>10}    variables {:.0}%",
+            "eliminated:",
+            ctx.stats.eliminated,
+            percent(ctx.stats.eliminated, ctx.formula.variables)
+        );
+        message!(
+            ctx.config.verbosity,
+            "{:<20} {:>10}    clauses {:.2} per eliminated",
+            "resolvents:",
+            ctx.stats.resolvents,
+            average(ctx.stats.resolvents, ctx.stats.eliminated)
+        );
+    }
+    message!(
+        ctx.config.verbosity,
+        "{:<20} {:13.2} seconds",
+        "process-time:",
+        elapsed_time
+    );
+}
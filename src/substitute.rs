@@ -0,0 +1,384 @@
+use crate::extend::push_witness;
+use crate::formula::{ClauseRef, Clauses};
+use crate::preprocessor::SATContext;
+use crate::proof::delete_clause;
+use std::collections::VecDeque;
+
+const UNVISITED: usize = usize::MAX;
+
+// Literals are nodes, with 'lit' and '-lit' next to each other as in the
+// occurrence lists.
+fn node(lit: i32) -> usize {
+    2 * (lit.unsigned_abs() as usize - 1) + (lit < 0) as usize
+}
+
+fn literal(node: usize) -> i32 {
+    let var = (node / 2 + 1) as i32;
+    if node % 2 == 1 {
+        -var
+    } else {
+        var
+    }
+}
+
+// Binary implication graph: every binary clause '(a b)' yields the edges
+// '-a -> b' and '-b -> a', labelled with the clause.
+struct Graph {
+    edges: Vec<Vec<(usize, ClauseRef)>>,
+}
+
+impl Graph {
+    fn new(ctx: &SATContext) -> Self {
+        let mut edges = vec![Vec::new(); 2 * ctx.formula.variables];
+        for &clause_id in ctx.formula.clauses.refs() {
+            let clause = &ctx.formula.clauses[clause_id];
+            if ctx.formula.clauses.is_garbage(clause_id) || clause.len() != 2 {
+                continue;
+            }
+            let (a, b) = (clause[0], clause[1]);
+            edges[node(-a)].push((node(b), clause_id));
+            edges[node(-b)].push((node(a), clause_id));
+        }
+        Graph { edges }
+    }
+
+    fn reversed(&self) -> Graph {
+        let mut edges = vec![Vec::new(); self.edges.len()];
+        for (from, targets) in self.edges.iter().enumerate() {
+            for &(to, clause_id) in targets {
+                edges[to].push((from, clause_id));
+            }
+        }
+        Graph { edges }
+    }
+
+    // Tarjan's algorithm without recursion, returning the strongly connected
+    // component of every node.
+    fn components(&self) -> Vec<usize> {
+        let nodes = self.edges.len();
+        let mut index = vec![UNVISITED; nodes];
+        let mut low = vec![0; nodes];
+        let mut component = vec![UNVISITED; nodes];
+        let mut stack = Vec::new();
+        let mut calls: Vec<(usize, usize)> = Vec::new();
+        let mut next_index = 0;
+        let mut components = 0;
+
+        for root in 0..nodes {
+            if index[root] != UNVISITED {
+                continue;
+            }
+            calls.push((root, 0));
+            while let Some(&mut (current, ref mut position)) = calls.last_mut() {
+                if *position == 0 {
+                    index[current] = next_index;
+                    low[current] = next_index;
+                    next_index += 1;
+                    stack.push(current);
+                }
+                if let Some(&(target, _)) = self.edges[current].get(*position) {
+                    *position += 1;
+                    if index[target] == UNVISITED {
+                        calls.push((target, 0));
+                    } else if component[target] == UNVISITED {
+                        low[current] = low[current].min(index[target]);
+                    }
+                    continue;
+                }
+                calls.pop();
+                if let Some(&(parent, _)) = calls.last() {
+                    low[parent] = low[parent].min(low[current]);
+                }
+                if low[current] == index[current] {
+                    while let Some(member) = stack.pop() {
+                        component[member] = components;
+                        if member == current {
+                            break;
+                        }
+                    }
+                    components += 1;
+                }
+            }
+        }
+        component
+    }
+
+    // Ids of the binary clauses within each component, which the
+    // equivalences between its literals are derived from.
+    fn equivalences(&self, component: &[usize], clauses: &Clauses) -> Vec<Vec<u64>> {
+        let mut ids = vec![Vec::new(); self.edges.len()];
+        for (from, targets) in self.edges.iter().enumerate() {
+            for &(to, clause_id) in targets {
+                if component[from] == component[to] {
+                    ids[component[from]].push(clauses.id(clause_id));
+                }
+            }
+        }
+        ids
+    }
+
+    // Breadth-first search from 'start' within its component, storing for
+    // every reached node the previous node and the clause of the edge.
+    fn search(&self, start: usize, component: &[usize], parent: &mut [(usize, ClauseRef)]) {
+        parent[start] = (start, UNVISITED);
+        let mut queue = VecDeque::from([start]);
+        while let Some(current) = queue.pop_front() {
+            for &(target, clause_id) in &self.edges[current] {
+                if component[target] == component[start] && parent[target].0 == UNVISITED {
+                    parent[target] = (current, clause_id);
+                    queue.push_back(target);
+                }
+            }
+        }
+    }
+}
+
+// Follows the search tree from 'node' back to its root, collecting the
+// clauses on the way.
+fn path(parent: &[(usize, ClauseRef)], mut node: usize) -> Vec<ClauseRef> {
+    let mut clauses = Vec::new();
+    while parent[node].1 != UNVISITED {
+        clauses.push(parent[node].1);
+        node = parent[node].0;
+    }
+    clauses
+}
+
+// Orders the candidate clauses into LRAT hints deriving 'clause' by unit
+// propagation on its negation, keeping only those which actually become
+// unit before the conflict.
+fn rup_hints(ctx: &mut SATContext, clause: &[i32], candidates: &[ClauseRef]) -> Vec<u64> {
+    let mut assigned: Vec<i32> = clause.iter().map(|&lit| -lit).collect();
+    for &lit in &assigned {
+        ctx.formula.marks.mark(lit);
+    }
+    let mut used = vec![false; candidates.len()];
+    let mut hints = Vec::new();
+    let mut conflict = false;
+    let mut progress = true;
+    while progress && !conflict {
+        progress = false;
+        for (position, &clause_id) in candidates.iter().enumerate() {
+            if used[position] {
+                continue;
+            }
+            let candidate = &ctx.formula.clauses[clause_id];
+            let marks = &ctx.formula.marks;
+            if candidate.iter().any(|&lit| marks.is_marked(lit)) {
+                continue;
+            }
+            let mut unassigned = candidate.iter().filter(|&&lit| !marks.is_marked(-lit));
+            let unit = unassigned.next().copied();
+            if unassigned.next().is_some() {
+                continue;
+            }
+            used[position] = true;
+            progress = true;
+            hints.push(ctx.formula.clauses.id(clause_id));
+            match unit {
+                Some(lit) => {
+                    ctx.formula.marks.mark(lit);
+                    assigned.push(lit);
+                }
+                None => {
+                    conflict = true;
+                    break;
+                }
+            }
+        }
+    }
+    for lit in assigned {
+        ctx.formula.marks.unmark(lit);
+    }
+    debug_assert!(conflict, "hints do not derive {:?}", clause);
+    hints
+}
+
+fn is_lrat(ctx: &SATContext) -> bool {
+    ctx.proof.as_ref().is_some_and(|proof| proof.is_lrat())
+}
+
+// Derives the empty clause from 'lit' and '-lit' being equivalent.
+fn inconsistent(ctx: &mut SATContext, graph: &Graph, component: &[usize], lit: i32) {
+    verbose!(
+        ctx.config.verbosity,
+        2,
+        "literal {} is equivalent to its negation",
+        lit
+    );
+    let equivalences = graph.equivalences(component, &ctx.formula.clauses);
+    let mut units = Vec::new();
+    for unit in [-lit, lit] {
+        let id = ctx.formula.next_id();
+        let antecedents = &equivalences[component[node(lit)]];
+        ctx.formula.clauses.derive(id, antecedents);
+        let hints = if is_lrat(ctx) {
+            // the path from '-unit' to 'unit' falsifies '-unit'
+            let mut parent = vec![(UNVISITED, UNVISITED); component.len()];
+            graph.search(node(-unit), component, &mut parent);
+            let mut candidates = path(&parent, node(unit));
+            candidates.reverse();
+            rup_hints(ctx, &[unit], &candidates)
+        } else {
+            Vec::new()
+        };
+        if let Some(proof) = &mut ctx.proof {
+            proof.add(id, &[unit], &hints);
+        }
+        units.push(id);
+    }
+    let id = ctx.formula.next_id();
+    ctx.formula.clauses.derive(id, &units);
+    if let Some(proof) = &mut ctx.proof {
+        proof.add(id, &[], &units);
+    }
+    ctx.formula.add_clause(id, &[], ctx.config.verbosity);
+    ctx.formula.empty_clause_exists = true;
+}
+
+/// Equivalent literal substitution: literals in the same strongly connected
+/// component of the binary implication graph are equivalent and replaced by
+/// the literal with the smallest variable of their component. The removed
+/// variables are recorded on the extension stack as two binary clauses.
+pub(crate) fn substitute(ctx: &mut SATContext) {
+    verbose!(
+        ctx.config.verbosity,
+        1,
+        "starting equivalent literal substitution"
+    );
+    let graph = Graph::new(ctx);
+    let component = graph.components();
+    let equivalences = graph.equivalences(&component, &ctx.formula.clauses);
+
+    // nodes are ordered by variable, so the first node of a component
+    // is its representative, and mirrored components get negated ones,
+    // where frozen variables are preferred as they cannot be replaced
+    let frozen = |node: usize| ctx.formula.is_frozen(literal(node).unsigned_abs() as usize);
+    let mut representative = vec![0; component.len()];
+    for prefer_frozen in [true, false] {
+        for node in 0..component.len() {
+            if representative[component[node]] == 0 && frozen(node) == prefer_frozen {
+                representative[component[node]] = literal(node);
+            }
+        }
+    }
+    for var in 1..=ctx.formula.variables as i32 {
+        if component[node(var)] == component[node(-var)] {
+            inconsistent(ctx, &graph, &component, var);
+            return;
+        }
+    }
+    let substitute: Vec<i32> = (0..component.len())
+        .map(|node| {
+            if frozen(node) {
+                literal(node)
+            } else {
+                representative[component[node]]
+            }
+        })
+        .collect();
+    let mapped = |lit: i32| substitute[node(lit)];
+
+    let mut substituted = 0;
+    for var in 1..=ctx.formula.variables as i32 {
+        let rep = mapped(var);
+        if rep == var {
+            continue;
+        }
+        LOG!(ctx.config.verbosity, "substituting {} by {}", var, rep);
+        ctx.formula.eliminated[var as usize] = true;
+        push_witness(ctx, var, vec![var, -rep]);
+        push_witness(ctx, -var, vec![-var, rep]);
+        substituted += 1;
+    }
+    ctx.stats.substituted += substituted;
+    if substituted == 0 {
+        return;
+    }
+
+    // hints follow the search trees from the representatives backwards
+    let reversed = graph.reversed();
+    // components are disjoint, so their search trees share one array
+    let mut parent = vec![(UNVISITED, UNVISITED); component.len()];
+    if is_lrat(ctx) {
+        for node in 0..component.len() {
+            if literal(node) == representative[component[node]] {
+                reversed.search(node, &component, &mut parent);
+            }
+        }
+    }
+
+    // all substituted clauses are added to the proof before the binary
+    // clauses justifying them are deleted
+    let mut replaced = Vec::new();
+    for clause_id in ctx.formula.clauses.refs().to_vec() {
+        let clause = &ctx.formula.clauses[clause_id];
+        if ctx.formula.clauses.is_garbage(clause_id) || clause.iter().all(|&lit| mapped(lit) == lit)
+        {
+            continue;
+        }
+        let mut literals = Vec::with_capacity(clause.len());
+        let mut tautological = false;
+        for &lit in clause {
+            let rep = mapped(lit);
+            if ctx.formula.marks.is_marked(-rep) {
+                tautological = true;
+                break;
+            }
+            if !ctx.formula.marks.is_marked(rep) {
+                ctx.formula.marks.mark(rep);
+                literals.push(rep);
+            }
+        }
+        for &lit in &literals {
+            ctx.formula.marks.unmark(lit);
+        }
+        if tautological {
+            replaced.push((clause_id, None));
+            continue;
+        }
+        let id = ctx.formula.next_id();
+        let clause = &ctx.formula.clauses[clause_id];
+        let mut antecedents = vec![ctx.formula.clauses.id(clause_id)];
+        for &lit in clause.iter().filter(|&&lit| mapped(lit) != lit) {
+            antecedents.extend(&equivalences[component[node(lit)]]);
+        }
+        antecedents.sort_unstable();
+        antecedents.dedup();
+        let hints = if is_lrat(ctx) {
+            let mut candidates = Vec::new();
+            for &lit in clause.iter().filter(|&&lit| mapped(lit) != lit) {
+                let mut chain = path(&parent, node(lit));
+                chain.reverse();
+                candidates.extend(chain);
+            }
+            candidates.push(clause_id);
+            rup_hints(ctx, &literals, &candidates)
+        } else {
+            Vec::new()
+        };
+        if let Some(proof) = &mut ctx.proof {
+            proof.add(id, &literals, &hints);
+        }
+        replaced.push((clause_id, Some((id, literals, antecedents))));
+    }
+    for (clause_id, substituted) in replaced {
+        match substituted {
+            None => delete_clause(ctx, clause_id),
+            Some((id, literals, antecedents)) => {
+                let clauses = &mut ctx.formula.clauses;
+                if let Some(proof) = &mut ctx.proof {
+                    proof.delete(clauses.id(clause_id), &clauses[clause_id]);
+                }
+                clauses.replace(clause_id, id, &literals, &antecedents);
+            }
+        }
+    }
+    verbose!(
+        ctx.config.verbosity,
+        1,
+        "substituted {} equivalent variables",
+        substituted
+    );
+    ctx.formula.collect_garbage_clauses(ctx.config.verbosity);
+}
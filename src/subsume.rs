@@ -0,0 +1,599 @@
+use crate::formula::{variable_signature, CNFFormula, ClauseRef, Marks};
+use crate::preprocessor::SATContext;
+use crate::proof::delete_clause;
+
+fn let_empty_clause_subsume_all_clauses(ctx: &mut SATContext) {
+    for clause_id in ctx.formula.clauses.refs().to_vec() {
+        if !ctx.formula.clauses.is_garbage(clause_id) && !ctx.formula.clauses[clause_id].is_empty()
+        {
+            delete_clause(ctx, clause_id);
+            ctx.stats.subsumed += 1;
+        }
+    }
+    // keep the id of a derived empty clause for the proof
+    let clauses = &ctx.formula.clauses;
+    let id = clauses
+        .refs()
+        .iter()
+        .find(|&&clause_id| !clauses.is_garbage(clause_id) && clauses[clause_id].is_empty())
+        .map_or(0, |&clause_id| clauses.id(clause_id));
+    ctx.formula.clauses.clear();
+    let empty = ctx.formula.clauses.push(id, &[]);
+    ctx.formula.clauses.mark_used(empty);
+    ctx.formula.connected = false;
+    ctx.formula
+        .matrix
+        .init(ctx.formula.variables, ctx.config.verbosity);
+}
+
+fn mark_clause(ctx: &mut SATContext, clause_id: ClauseRef) {
+    for &lit in &ctx.formula.clauses[clause_id] {
+        ctx.formula.marks.mark(lit);
+    }
+}
+
+fn unmark_clause(ctx: &mut SATContext, clause_id: ClauseRef) {
+    for &lit in &ctx.formula.clauses[clause_id] {
+        ctx.formula.marks.unmark(lit);
+    }
+}
+
+// Outcome of checking a clause 'c' against a candidate 'd'.
+enum Check {
+    Unrelated,
+    // The larger clause is subsumed by the given clause.
+    Subsumed(ClauseRef),
+    // Self-subsuming resolution removes this literal from the larger clause,
+    // justified by the given clause.
+    Strengthened(i32, ClauseRef),
+}
+
+fn strengthen_clause(ctx: &mut SATContext, clause_id: ClauseRef, lit: i32, antecedent: ClauseRef) {
+    LOG!(
+        ctx.config.verbosity,
+        "strengthening clause {:?} by removing {}",
+        &ctx.formula.clauses[clause_id],
+        lit
+    );
+    let id = ctx.formula.next_id();
+    let clauses = &mut ctx.formula.clauses;
+    clauses.mark_used(antecedent);
+    let antecedent_id = clauses.id(antecedent);
+    let original = clauses[clause_id].to_vec();
+    let original_id = clauses.id(clause_id);
+    let literals: Vec<i32> = original
+        .iter()
+        .copied()
+        .filter(|&other| other != lit)
+        .collect();
+    clauses.replace(clause_id, id, &literals, &[antecedent_id, original_id]);
+    if let Some(proof) = &mut ctx.proof {
+        // the antecedent propagates '-lit' which falsifies the original
+        proof.add(id, &literals, &[antecedent_id, original_id]);
+        proof.delete(original_id, &original);
+    }
+    ctx.stats.strengthened += 1;
+    if literals.is_empty() {
+        verbose!(ctx.config.verbosity, 2, "strengthened to empty clause");
+        ctx.formula.empty_clause_exists = true;
+    }
+}
+
+// Whether the signatures allow 'c' to subsume 'd', or to strengthen it if
+// strengthening is enabled.
+fn signatures_match(c: u64, d: u64, strengthen: bool) -> bool {
+    if strengthen {
+        variable_signature(c) & !variable_signature(d) == 0
+    } else {
+        c & !d == 0
+    }
+}
+
+fn forward_check(ctx: &mut SATContext, clause_id: ClauseRef) -> Check {
+    let strengthen = ctx.config.strengthen;
+    let clauses = &ctx.formula.clauses;
+    let clause = &clauses[clause_id];
+    let signature = clauses.signature(clause_id);
+
+    for &lit in clause {
+        let negated = if strengthen { -lit } else { lit };
+        for candidate_lit in [lit, negated] {
+            'inner: for &d_id in &ctx.formula.matrix[candidate_lit] {
+                // only full occurrence lists contain the clause itself
+                if d_id == clause_id || clauses.is_garbage(d_id) {
+                    continue;
+                }
+                let d = &clauses[d_id];
+                // strengthening may have shrunk 'clause' below earlier ones
+                if d.len() > clause.len() {
+                    continue;
+                }
+                ctx.stats.checked += 1;
+                if !signatures_match(clauses.signature(d_id), signature, strengthen) {
+                    ctx.stats.filtered += 1;
+                    continue;
+                }
+
+                let mut flipped = 0;
+                for &other in d {
+                    if ctx.formula.marks.is_marked(other) {
+                        continue;
+                    }
+                    if strengthen && flipped == 0 && ctx.formula.marks.is_marked(-other) {
+                        flipped = other;
+                        continue;
+                    }
+                    continue 'inner;
+                }
+                if flipped == 0 {
+                    LOG!(ctx.config.verbosity, "subsuming clause {:?}", d);
+                    LOG!(ctx.config.verbosity, "subsumed clause {:?}", clause);
+                    return Check::Subsumed(d_id);
+                }
+                LOG!(ctx.config.verbosity, "strengthening clause {:?}", d);
+                return Check::Strengthened(-flipped, d_id);
+            }
+            if !strengthen {
+                break;
+            }
+        }
+    }
+
+    Check::Unrelated
+}
+
+fn forward_subsumed(ctx: &mut SATContext, clause_id: ClauseRef) -> bool {
+    mark_clause(ctx, clause_id);
+
+    loop {
+        match forward_check(ctx, clause_id) {
+            Check::Unrelated => break,
+            Check::Subsumed(subsumer) => {
+                ctx.formula.clauses.mark_used(subsumer);
+                delete_clause(ctx, clause_id);
+                ctx.stats.subsumed += 1;
+                break;
+            }
+            Check::Strengthened(lit, antecedent) => {
+                ctx.formula.marks.unmark(lit);
+                strengthen_clause(ctx, clause_id, lit, antecedent);
+                if ctx.formula.empty_clause_exists {
+                    break;
+                }
+            }
+        }
+    }
+
+    unmark_clause(ctx, clause_id);
+    ctx.formula.clauses.is_garbage(clause_id)
+}
+
+fn occurrences(ctx: &SATContext, lit: i32) -> usize {
+    ctx.formula.matrix[lit].len()
+}
+
+fn least_occuring(
+    ctx: &SATContext,
+    clause_id: ClauseRef,
+    both_signs: bool,
+    min_occs_ext: &mut usize,
+) -> i32 {
+    let mut min_lit = 0;
+    let mut min_occs = usize::MAX;
+
+    for &lit in &ctx.formula.clauses[clause_id] {
+        let mut occs = occurrences(ctx, lit);
+        if both_signs {
+            occs += occurrences(ctx, -lit);
+        }
+        if occs < min_occs {
+            min_occs = occs;
+            min_lit = lit;
+            if occs == 0 {
+                break;
+            }
+        }
+    }
+
+    LOG!(
+        ctx.config.verbosity,
+        "literal {} connected {} times",
+        min_lit,
+        min_occs
+    );
+
+    *min_occs_ext = min_occs;
+    min_lit
+}
+
+fn connect_least_occuring(ctx: &mut SATContext, clause_id: ClauseRef) {
+    let min_occs = &mut 0;
+    let lit = least_occuring(ctx, clause_id, false, min_occs);
+    ctx.formula
+        .connect_lit(lit, clause_id, ctx.config.verbosity);
+}
+
+fn forward_subsumption(ctx: &mut SATContext) {
+    verbose!(ctx.config.verbosity, 1, "starting forward subsumption");
+    // sort ascending by clause size
+    ctx.formula.sort_clauses_by_key(|c| c.len());
+    for clause_id in ctx.formula.clauses.refs().to_vec() {
+        if !forward_subsumed(ctx, clause_id) {
+            if ctx.formula.empty_clause_exists {
+                return;
+            }
+            connect_least_occuring(ctx, clause_id);
+        }
+    }
+}
+
+fn backward_check(ctx: &SATContext, c_id: ClauseRef, c_len: usize, d_id: ClauseRef) -> Check {
+    let d = &ctx.formula.clauses[d_id];
+    if ctx.formula.clauses.is_garbage(d_id) || d.len() < c_len {
+        return Check::Unrelated;
+    }
+    let mut marked = 0;
+    let mut flipped = 0;
+    for &lit in d {
+        if ctx.formula.marks.is_marked(lit) {
+            marked += 1;
+        } else if ctx.config.strengthen && ctx.formula.marks.is_marked(-lit) {
+            if flipped != 0 {
+                return Check::Unrelated;
+            }
+            flipped = lit;
+        } else {
+            continue;
+        }
+        if marked == c_len {
+            return Check::Subsumed(c_id);
+        }
+    }
+    if flipped != 0 && marked + 1 == c_len {
+        return Check::Strengthened(flipped, c_id);
+    }
+    Check::Unrelated
+}
+
+pub(crate) fn backward_subsume(ctx: &mut SATContext, clause_id: ClauseRef) {
+    LOG!(
+        ctx.config.verbosity,
+        "backward subsuming clause {}",
+        clause_id
+    );
+    let mut min_occs = usize::MAX;
+    // backward strengthening also visits the negated occurrences
+    let strengthen = ctx.config.strengthen;
+    let min_lit = least_occuring(ctx, clause_id, strengthen, &mut min_occs);
+    if min_lit == 0 {
+        return;
+    }
+    if min_occs == 0 {
+        return;
+    }
+    mark_clause(ctx, clause_id);
+    let c_len = ctx.formula.clauses[clause_id].len();
+    let c_signature = ctx.formula.clauses.signature(clause_id);
+    let mut subsumed = Vec::new();
+    let mut strengthened = Vec::new();
+    let negated = if strengthen { -min_lit } else { min_lit };
+    for candidate_lit in [min_lit, negated] {
+        for &d_id in &ctx.formula.matrix[candidate_lit] {
+            if d_id == clause_id {
+                continue;
+            }
+            ctx.stats.checked += 1;
+            if !signatures_match(c_signature, ctx.formula.clauses.signature(d_id), strengthen) {
+                ctx.stats.filtered += 1;
+                continue;
+            }
+            match backward_check(ctx, clause_id, c_len, d_id) {
+                Check::Unrelated => {}
+                Check::Subsumed(_) => {
+                    LOG!(
+                        ctx.config.verbosity,
+                        "backward subsumed {:?}",
+                        &ctx.formula.clauses[d_id]
+                    );
+                    subsumed.push(d_id);
+                }
+                Check::Strengthened(lit, _) => strengthened.push((d_id, lit)),
+            }
+        }
+        if candidate_lit == negated {
+            break;
+        }
+    }
+    unmark_clause(ctx, clause_id);
+
+    if !subsumed.is_empty() {
+        ctx.formula.clauses.mark_used(clause_id);
+    }
+    for d_id in subsumed {
+        delete_clause(ctx, d_id);
+        ctx.stats.subsumed += 1;
+    }
+    for &(d_id, lit) in &strengthened {
+        strengthen_clause(ctx, d_id, lit, clause_id);
+    }
+    let mut removed: Vec<i32> = strengthened.iter().map(|&(_, lit)| lit).collect();
+    removed.sort_unstable();
+    removed.dedup();
+    for lit in removed {
+        ctx.formula.flush_occurrences(lit, ctx.config.verbosity);
+    }
+}
+
+fn backward_subsumption(ctx: &mut SATContext) {
+    verbose!(ctx.config.verbosity, 1, "starting backward subsumption");
+    // sort descending by clause size
+    ctx.formula
+        .sort_clauses_by_key(|c| std::cmp::Reverse(c.len()));
+    for clause_id in ctx.formula.clauses.refs().to_vec() {
+        backward_subsume(ctx, clause_id);
+        if ctx.formula.empty_clause_exists {
+            return;
+        }
+        ctx.formula.connect_clause(clause_id, ctx.config.verbosity);
+    }
+}
+
+// Clauses subsumed by the candidates assigned to one thread and the
+// candidates subsuming them, together with the number of checks spent.
+#[derive(Default)]
+struct Partition {
+    subsumed: Vec<ClauseRef>,
+    subsumers: Vec<ClauseRef>,
+    checked: usize,
+    filtered: usize,
+}
+
+// Checks every 'threads'-th candidate, starting with 'first', against the
+// clauses before it, which are the ones at smaller references after sorting.
+fn backward_partition(
+    formula: &CNFFormula,
+    candidates: &[ClauseRef],
+    first: usize,
+    threads: usize,
+) -> Partition {
+    let clauses = &formula.clauses;
+    let mut marks = Marks::new();
+    marks.init(formula.variables, -1);
+    let mut partition = Partition::default();
+    for &c_id in candidates.iter().skip(first).step_by(threads) {
+        let c = &clauses[c_id];
+        let Some(&min_lit) = c.iter().min_by_key(|&&lit| formula.matrix[lit].len()) else {
+            continue;
+        };
+        let c_signature = clauses.signature(c_id);
+        for &lit in c {
+            marks.mark(lit);
+        }
+        let before = partition.subsumed.len();
+        for &d_id in &formula.matrix[min_lit] {
+            if d_id >= c_id {
+                break;
+            }
+            partition.checked += 1;
+            if !signatures_match(c_signature, clauses.signature(d_id), false) {
+                partition.filtered += 1;
+                continue;
+            }
+            let d = &clauses[d_id];
+            if d.len() >= c.len()
+                && d.iter().filter(|&&lit| marks.is_marked(lit)).count() == c.len()
+            {
+                partition.subsumed.push(d_id);
+            }
+        }
+        if partition.subsumed.len() > before {
+            partition.subsumers.push(c_id);
+        }
+        for &lit in c {
+            marks.unmark(lit);
+        }
+    }
+    partition
+}
+
+// Removes the same clauses as 'backward_subsumption' without strengthening,
+// namely those with a subset among the clauses after them. The candidates
+// are checked on 'threads' threads against read-only occurrence lists and
+// the subsumed clauses are deleted afterwards in the order of the arena, so
+// the result does not depend on the number of threads.
+fn parallel_backward_subsumption(ctx: &mut SATContext, threads: usize) {
+    verbose!(
+        ctx.config.verbosity,
+        1,
+        "starting backward subsumption on {} threads",
+        threads
+    );
+    ctx.formula
+        .sort_clauses_by_key(|c| std::cmp::Reverse(c.len()));
+    // occurrence lists are filled in arena order and thus sorted
+    ctx.formula.connect_all_clauses(ctx.config.verbosity);
+    let formula = &ctx.formula;
+    let candidates = formula.clauses.refs();
+    let partitions: Vec<Partition> = std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads)
+            .map(|first| {
+                scope.spawn(move || backward_partition(formula, candidates, first, threads))
+            })
+            .collect();
+        workers
+            .into_iter()
+            .map(|worker| worker.join().expect("subsumption thread panicked"))
+            .collect()
+    });
+    let mut subsumed = Vec::new();
+    for partition in partitions {
+        ctx.stats.checked += partition.checked;
+        ctx.stats.filtered += partition.filtered;
+        subsumed.extend(partition.subsumed);
+        for c_id in partition.subsumers {
+            ctx.formula.clauses.mark_used(c_id);
+        }
+    }
+    subsumed.sort_unstable();
+    subsumed.dedup();
+    for d_id in subsumed {
+        LOG!(
+            ctx.config.verbosity,
+            "backward subsumed {:?}",
+            &ctx.formula.clauses[d_id]
+        );
+        delete_clause(ctx, d_id);
+        ctx.stats.subsumed += 1;
+    }
+}
+
+fn dirty_clauses(ctx: &SATContext) -> Vec<ClauseRef> {
+    let clauses = &ctx.formula.clauses;
+    let mut dirty: Vec<ClauseRef> = clauses
+        .refs()
+        .iter()
+        .copied()
+        .filter(|&clause_id| !clauses.is_garbage(clause_id) && clauses.is_dirty(clause_id))
+        .collect();
+    // smaller clauses subsume more, so they go first
+    dirty.sort_by_key(|&clause_id| clauses[clause_id].len());
+    dirty
+}
+
+// Checks the dirty clauses against the full occurrence lists, first whether
+// each is subsumed itself and then which clauses it subsumes. Clauses
+// strengthened on the way become dirty again.
+fn subsume_dirty(ctx: &mut SATContext, dirty: &[ClauseRef]) {
+    for &clause_id in dirty {
+        if ctx.formula.clauses.is_garbage(clause_id) {
+            continue;
+        }
+        if !forward_subsumed(ctx, clause_id) && !ctx.formula.empty_clause_exists {
+            backward_subsume(ctx, clause_id);
+        }
+        if ctx.formula.empty_clause_exists {
+            break;
+        }
+        ctx.formula.clauses.clear_dirty(clause_id);
+    }
+}
+
+// Runs 'subsume_dirty' on rebuilt occurrence lists and returns the number of
+// clauses checked.
+fn combined_round(ctx: &mut SATContext) -> usize {
+    let dirty = dirty_clauses(ctx);
+    if dirty.is_empty() {
+        return 0;
+    }
+    ctx.formula.connect_all_clauses(ctx.config.verbosity);
+    subsume_dirty(ctx, &dirty);
+    dirty.len()
+}
+
+// A forward sweep over all clauses followed by rounds of combined forward
+// and backward subsumption of the clauses strengthened in the round before,
+// until nothing changes or the round limit is hit.
+fn combined_subsumption(ctx: &mut SATContext) {
+    verbose!(ctx.config.verbosity, 1, "starting combined subsumption");
+    ctx.formula.clauses.clear_all_dirty();
+    let mut round = 0;
+    while round < ctx.config.subsume_rounds && !ctx.formula.empty_clause_exists {
+        let subsumed = ctx.stats.subsumed;
+        let strengthened = ctx.stats.strengthened;
+        let checked = if round == 0 {
+            let clauses = ctx.formula.clauses.len();
+            forward_subsumption(ctx);
+            clauses
+        } else {
+            combined_round(ctx)
+        };
+        if checked == 0 {
+            break;
+        }
+        round += 1;
+        ctx.stats.rounds += 1;
+        let counts = (
+            ctx.stats.subsumed - subsumed,
+            ctx.stats.strengthened - strengthened,
+        );
+        ctx.stats.round_counts.push(counts);
+        verbose!(
+            ctx.config.verbosity,
+            1,
+            "round {} checked {} clauses, subsumed {} and strengthened {}",
+            round,
+            checked,
+            counts.0,
+            counts.1
+        );
+        if ctx.stats.subsumed == subsumed && ctx.stats.strengthened == strengthened {
+            break;
+        }
+    }
+}
+
+/// Which subsumption algorithm [`subsume`] runs.
+pub(crate) enum Mode {
+    Forward,
+    Backward,
+    Combined,
+}
+
+/// Incremental subsumption after the formula was simplified: only clauses
+/// added or strengthened since are checked forward and backward, against
+/// occurrence lists which are connected once and then kept up to date.
+pub(crate) fn subsume_incrementally(ctx: &mut SATContext) {
+    if ctx.formula.empty_clause_exists {
+        let_empty_clause_subsume_all_clauses(ctx);
+        return;
+    }
+    let mut dirty = dirty_clauses(ctx);
+    verbose!(
+        ctx.config.verbosity,
+        1,
+        "starting incremental subsumption of {} clauses",
+        dirty.len()
+    );
+    // once connected, added clauses are connected right away
+    if !ctx.formula.connected {
+        ctx.formula.connect_all_clauses(ctx.config.verbosity);
+        ctx.formula.connected = true;
+    }
+    while !dirty.is_empty() {
+        subsume_dirty(ctx, &dirty);
+        if ctx.formula.empty_clause_exists {
+            let_empty_clause_subsume_all_clauses(ctx);
+            return;
+        }
+        dirty = dirty_clauses(ctx);
+    }
+    ctx.formula.collect_garbage_clauses(ctx.config.verbosity);
+}
+
+pub(crate) fn subsume(ctx: &mut SATContext, mode: Mode) {
+    // the occurrence lists are rebuilt or dropped in any case
+    ctx.formula.connected = false;
+    if ctx.formula.empty_clause_exists {
+        let_empty_clause_subsume_all_clauses(ctx);
+    } else {
+        verbose!(ctx.config.verbosity, 1, "starting to simplify formula");
+        // occurrence lists are rebuilt from scratch by every sweep
+        ctx.formula
+            .matrix
+            .init(ctx.formula.variables, ctx.config.verbosity);
+        match mode {
+            Mode::Forward => forward_subsumption(ctx),
+            Mode::Backward if ctx.config.threads > 1 && !ctx.config.strengthen => {
+                parallel_backward_subsumption(ctx, ctx.config.threads)
+            }
+            Mode::Backward => backward_subsumption(ctx),
+            Mode::Combined => combined_subsumption(ctx),
+        }
+        verbose!(ctx.config.verbosity, 1, "simplification complete");
+        if ctx.formula.empty_clause_exists {
+            let_empty_clause_subsume_all_clauses(ctx);
+        } else {
+            ctx.formula.collect_garbage_clauses(ctx.config.verbosity);
+        }
+    }
+}
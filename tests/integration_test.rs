@@ -18,6 +18,13 @@ fn extract_hash(content: &str) -> Result<String, Box<dyn std::error::Error>> {
 }
 
 fn run_test_case(test_name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    run_test_case_with_args(test_name, &[])
+}
+
+fn run_test_case_with_args(
+    test_name: &str,
+    args: &[&str],
+) -> Result<(), Box<dyn std::error::Error>> {
     let current_dir = std::env::current_dir().unwrap();
     let cnf_path = current_dir
         .join(TEST_DIR)
@@ -47,7 +54,7 @@ fn run_test_case(test_name: &str) -> Result<(), Box<dyn std::error::Error>> {
 
     let executable_path = current_dir.join("target/debug").join(EXECUTABLE_NAME);
     let mut cmd = Command::new(executable_path);
-    cmd.arg("-s").arg(&cnf_path).arg(&output_path);
+    cmd.arg("-s").args(args).arg(&cnf_path).arg(&output_path);
 
     let output: Output = cmd.output()?;
 
@@ -107,3 +114,435 @@ fn test_trivial1() -> Result<(), Box<dyn std::error::Error>> {
 fn test_trivial2() -> Result<(), Box<dyn std::error::Error>> {
     run_test_case("trivial2")
 }
+
+#[test]
+fn test_block1() -> Result<(), Box<dyn std::error::Error>> {
+    run_test_case_with_args("block1", &["--block", "--block-limit", "2"])
+}
+
+#[test]
+fn test_parallel_binbin1() -> Result<(), Box<dyn std::error::Error>> {
+    run_test_case_with_args("binbin1", &["-b", "-j", "4"])
+}
+
+// Backward subsumption on four threads has to print exactly the same
+// formula as on one.
+fn run_parallel_case(test_name: &str) {
+    let current_dir = std::env::current_dir().unwrap();
+    let cnf_path = current_dir
+        .join(TEST_DIR)
+        .join(test_name)
+        .with_extension(CNF_EXT);
+    let executable_path = current_dir.join("target/debug").join(EXECUTABLE_NAME);
+    let outputs: Vec<Vec<u8>> = [&["-b"][..], &["-b", "-j", "4"]]
+        .iter()
+        .map(|args| {
+            let output = Command::new(&executable_path)
+                .args(["-q", "-s"])
+                .args(*args)
+                .arg(&cnf_path)
+                .output()
+                .unwrap();
+            assert!(output.status.success());
+            output.stdout
+        })
+        .collect();
+    assert_eq!(
+        String::from_utf8_lossy(&outputs[1]),
+        String::from_utf8_lossy(&outputs[0])
+    );
+}
+
+#[test]
+fn test_parallel_combined1() {
+    run_parallel_case("combined1");
+}
+
+#[test]
+fn test_parallel1() {
+    run_parallel_case("parallel1");
+}
+
+#[test]
+fn test_duplicates1() -> Result<(), Box<dyn std::error::Error>> {
+    run_test_case("duplicates1")
+}
+
+#[test]
+fn test_eliminate1() -> Result<(), Box<dyn std::error::Error>> {
+    run_test_case_with_args("eliminate1", &["--eliminate", "--elim-occ-limit", "1"])
+}
+
+#[test]
+fn test_propagate1() -> Result<(), Box<dyn std::error::Error>> {
+    run_test_case_with_args("propagate1", &["--propagate"])
+}
+
+#[test]
+fn test_probe1() -> Result<(), Box<dyn std::error::Error>> {
+    run_test_case_with_args("probe1", &["-p"])
+}
+
+#[test]
+fn test_substitute1() -> Result<(), Box<dyn std::error::Error>> {
+    run_test_case_with_args("substitute1", &["--substitute"])
+}
+
+#[test]
+fn test_pure1() -> Result<(), Box<dyn std::error::Error>> {
+    run_test_case_with_args("pure1", &["--pure"])
+}
+
+#[test]
+fn test_strengthen1() -> Result<(), Box<dyn std::error::Error>> {
+    run_test_case_with_args("strengthen1", &["--strengthen"])
+}
+
+#[test]
+fn test_freeze1() -> Result<(), Box<dyn std::error::Error>> {
+    run_test_case_with_args(
+        "freeze1",
+        &["--eliminate", "--freeze", "tests/test_cases/freeze1.vars"],
+    )
+}
+
+#[test]
+fn test_freeze_invalid() {
+    for value in ["1,,2", "1,2,x"] {
+        let diagnostic = format!("Invalid value '{}' for --freeze", value);
+        run_error_case_with_args("freeze1", &["--freeze", value], 1, &diagnostic);
+    }
+}
+
+#[test]
+fn test_freeze_out_of_range() {
+    run_error_case_with_args(
+        "freeze1",
+        &["--freeze", "1,100"],
+        1,
+        "Frozen variable 100 exceeds the 4 variables",
+    );
+}
+
+#[test]
+fn test_combined1() -> Result<(), Box<dyn std::error::Error>> {
+    run_test_case_with_args("combined1", &["-f", "-b", "--strengthen"])
+}
+
+#[test]
+fn test_compact1() -> Result<(), Box<dyn std::error::Error>> {
+    let mapping_path = std::env::temp_dir().join("babysub-compact1.mapping");
+    run_test_case_with_args("compact1", &["--compact", mapping_path.to_str().unwrap()])?;
+    let mapping = fs::read_to_string(&mapping_path)?;
+    let _ = fs::remove_file(&mapping_path);
+    let golden = fs::read_to_string(std::path::Path::new(TEST_DIR).join("compact1.mapping"))?;
+    assert_eq!(mapping, golden);
+    Ok(())
+}
+
+#[test]
+fn test_gzipped1() -> Result<(), Box<dyn std::error::Error>> {
+    run_test_case("gzipped1")
+}
+
+#[test]
+fn test_compressed_stdin() {
+    let current_dir = std::env::current_dir().unwrap();
+    let input = fs::read(current_dir.join(TEST_DIR).join("gzipped1.cnf")).unwrap();
+    let golden = fs::read_to_string(current_dir.join(TEST_DIR).join("gzipped1.golden")).unwrap();
+    let executable_path = current_dir.join("target/debug").join(EXECUTABLE_NAME);
+    let output = Command::new(executable_path)
+        .args(["-q", "-s"])
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    let output = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(
+        extract_hash(&output).unwrap(),
+        extract_hash(&golden).unwrap()
+    );
+}
+
+fn run_compression_round_trip(test_name: &str, extension: &str, args: &[&str]) {
+    let current_dir = std::env::current_dir().unwrap();
+    let test_path = current_dir.join(TEST_DIR).join(test_name);
+    let compressed_path =
+        std::env::temp_dir().join(format!("babysub-{}.cnf.{}", test_name, extension));
+    let executable_path = current_dir.join("target/debug").join(EXECUTABLE_NAME);
+    Command::new(&executable_path)
+        .arg("-q")
+        .args(args)
+        .arg(test_path.with_extension(CNF_EXT))
+        .arg(&compressed_path)
+        .assert()
+        .success();
+    let output = Command::new(&executable_path)
+        .args(["-q", "-s"])
+        .arg(&compressed_path)
+        .output()
+        .unwrap();
+    let _ = fs::remove_file(&compressed_path);
+    let output = String::from_utf8(output.stdout).unwrap();
+    let golden = fs::read_to_string(test_path.with_extension(GOLDEN_EXT)).unwrap();
+    assert_eq!(
+        extract_hash(&output).unwrap(),
+        extract_hash(&golden).unwrap()
+    );
+}
+
+#[test]
+fn test_zstd_round_trip() {
+    run_compression_round_trip("binbin2", "zst", &["--compression-level", "1"]);
+}
+
+#[test]
+fn test_lz4_round_trip() {
+    run_compression_round_trip("binbin2", "lz4", &[]);
+}
+
+#[test]
+fn test_lz4_level() {
+    let output_path = std::env::temp_dir().join("babysub-lz4-level.cnf.lz4");
+    let current_dir = std::env::current_dir().unwrap();
+    let executable_path = current_dir.join("target/debug").join(EXECUTABLE_NAME);
+    Command::new(executable_path)
+        .args(["-q", "--compression-level", "1"])
+        .arg(current_dir.join(TEST_DIR).join("binbin2.cnf"))
+        .arg(&output_path)
+        .assert()
+        .code(5)
+        .stderr(predicates::str::contains("lz4 has no levels"));
+    let _ = fs::remove_file(&output_path);
+}
+
+#[test]
+fn test_compressed_proof() {
+    let current_dir = std::env::current_dir().unwrap();
+    let executable_path = current_dir.join("target/debug").join(EXECUTABLE_NAME);
+    let mut proofs = Vec::new();
+    for extension in ["proof", "proof.zst"] {
+        let proof_path =
+            std::env::temp_dir().join(format!("babysub-compressed-proof.{}", extension));
+        Command::new(&executable_path)
+            .args(["-q", "--strengthen", "--proof"])
+            .arg(&proof_path)
+            .arg(current_dir.join(TEST_DIR).join("strengthen1.cnf"))
+            .assert()
+            .success();
+        proofs.push(fs::read(&proof_path).unwrap());
+        let _ = fs::remove_file(&proof_path);
+    }
+    assert_eq!(zstd::decode_all(&proofs[1][..]).unwrap(), proofs[0]);
+}
+
+fn run_error_case(test_name: &str, exit_code: i32, diagnostic: &str) {
+    run_error_case_with_args(test_name, &[], exit_code, diagnostic);
+}
+
+fn run_error_case_with_args(test_name: &str, args: &[&str], exit_code: i32, diagnostic: &str) {
+    let current_dir = std::env::current_dir().unwrap();
+    let cnf_path = current_dir
+        .join(TEST_DIR)
+        .join(test_name)
+        .with_extension(CNF_EXT);
+    let executable_path = current_dir.join("target/debug").join(EXECUTABLE_NAME);
+    Command::new(executable_path)
+        .arg("-q")
+        .args(args)
+        .arg(&cnf_path)
+        .assert()
+        .code(exit_code)
+        .stderr(predicates::str::contains(diagnostic));
+}
+
+#[test]
+fn test_missing_file() {
+    run_error_case("does_not_exist", 2, "Failed to open input file");
+}
+
+#[test]
+fn test_unwritable_proof() {
+    run_error_case_with_args(
+        "binbin1",
+        &["--proof", "does_not_exist/binbin1.proof"],
+        2,
+        "Failed to create output file 'does_not_exist/binbin1.proof'",
+    );
+}
+
+#[test]
+fn test_usage_error() {
+    run_error_case_with_args(
+        "binbin1",
+        &["--strict", "--relaxed"],
+        1,
+        "cannot be used with",
+    );
+}
+
+#[test]
+fn test_invalid_literal() {
+    run_error_case("invalid_literal", 3, "at line 2 column 3");
+}
+
+#[test]
+fn test_out_of_range() {
+    run_error_case(
+        "out_of_range",
+        4,
+        "literal 2 exceeds the 1 declared variables",
+    );
+}
+
+#[test]
+fn test_strict_junk() {
+    run_error_case_with_args("strict_junk", &["--strict"], 3, "at line 5 column 1 in");
+}
+
+#[test]
+fn test_strict_count() {
+    run_error_case_with_args("strict_count", &["--strict"], 4, "at line 1 column 9 in");
+}
+
+#[test]
+fn test_strict_unterminated() {
+    run_error_case_with_args(
+        "strict_unterminated",
+        &["--strict"],
+        3,
+        "Clause not terminated by zero.",
+    );
+}
+
+#[test]
+fn test_tokens1() -> Result<(), Box<dyn std::error::Error>> {
+    run_test_case_with_args("tokens1", &["--strict"])
+}
+
+#[test]
+fn test_plus1() -> Result<(), Box<dyn std::error::Error>> {
+    run_test_case("plus1")
+}
+
+#[test]
+fn test_strict_plus() {
+    run_error_case_with_args("plus1", &["--strict"], 3, "at line 3 column 1 in");
+}
+
+#[test]
+fn test_relaxed1() -> Result<(), Box<dyn std::error::Error>> {
+    run_test_case_with_args("relaxed1", &["--relaxed"])
+}
+
+#[test]
+fn test_headers1() -> Result<(), Box<dyn std::error::Error>> {
+    run_test_case("headers1")?;
+    run_test_case_with_args("headers1", &["--relaxed"])
+}
+
+#[test]
+fn test_suffix_mismatch() {
+    let current_dir = std::env::current_dir().unwrap();
+    let executable_path = current_dir.join("target/debug").join(EXECUTABLE_NAME);
+    Command::new(executable_path)
+        .arg("-q")
+        .arg(current_dir.join(TEST_DIR).join("mismatch1.gz"))
+        .assert()
+        .code(5)
+        .stderr(predicates::str::contains(
+            "is named like a gzip file but contains uncompressed data",
+        ));
+}
+
+#[test]
+fn test_extend1() {
+    let current_dir = std::env::current_dir().unwrap();
+    let test_path = current_dir.join(TEST_DIR).join("extend1");
+    let executable_path = current_dir.join("target/debug").join(EXECUTABLE_NAME);
+    Command::new(executable_path)
+        .arg("extend")
+        .arg(test_path.with_extension("stack"))
+        .arg(test_path.with_extension("solution"))
+        .assert()
+        .success()
+        .stdout(predicates::str::contains(
+            "s SATISFIABLE\nv 1 -2 -3 4 5 6 0\n",
+        ));
+}
+
+#[test]
+fn test_extend_compacted() {
+    let current_dir = std::env::current_dir().unwrap();
+    let test_path = current_dir.join(TEST_DIR).join("compact1");
+    let executable_path = current_dir.join("target/debug").join(EXECUTABLE_NAME);
+    Command::new(executable_path)
+        .arg("extend")
+        .arg("--mapping")
+        .arg(test_path.with_extension("mapping"))
+        .arg(test_path.with_extension("stack"))
+        .arg(test_path.with_extension("solution"))
+        .assert()
+        .success()
+        .stdout(predicates::str::contains(
+            "s SATISFIABLE\nv -1 -2 3 -4 5 0\n",
+        ));
+}
+
+fn run_proof_case(test_name: &str, args: &[&str], expected: &str) {
+    let current_dir = std::env::current_dir().unwrap();
+    let cnf_path = current_dir
+        .join(TEST_DIR)
+        .join(test_name)
+        .with_extension(CNF_EXT);
+    let proof_path =
+        std::env::temp_dir().join(format!("babysub-{}{}.proof", test_name, args.concat()));
+    let executable_path = current_dir.join("target/debug").join(EXECUTABLE_NAME);
+    Command::new(executable_path)
+        .arg("-q")
+        .args(args)
+        .arg("--proof")
+        .arg(&proof_path)
+        .arg(&cnf_path)
+        .assert()
+        .success();
+    let proof = fs::read_to_string(&proof_path).unwrap();
+    let _ = fs::remove_file(&proof_path);
+    assert_eq!(proof, expected);
+}
+
+#[test]
+fn test_proof1() {
+    run_proof_case(
+        "strengthen1",
+        &["--strengthen"],
+        "2 0\nd -1 2 0\nd 2 3 0\n3 0\nd -2 3 0\n",
+    );
+}
+
+#[test]
+fn test_lrat1() {
+    run_proof_case(
+        "strengthen1",
+        &["--strengthen", "--lrat"],
+        "5 2 0 1 2 0\n5 d 2 0\n5 d 3 0\n6 3 0 5 4 0\n6 d 4 0\n",
+    );
+}
+
+#[test]
+fn test_bench_parse_input() {
+    run_error_case_with_args("gzipped1", &["--bench-parse"], 1, "always streamed");
+    let current_dir = std::env::current_dir().unwrap();
+    let executable_path = current_dir.join("target/debug").join(EXECUTABLE_NAME);
+    Command::new(&executable_path)
+        .arg("--bench-parse")
+        .write_stdin("p cnf 1 1\n1 0\n")
+        .assert()
+        .code(1)
+        .stderr(predicates::str::contains("standard input"));
+    Command::new(&executable_path)
+        .arg("--bench-parse")
+        .arg(current_dir.join(TEST_DIR).join("tokens1.cnf"))
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("mapped:"));
+}
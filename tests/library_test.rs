@@ -0,0 +1,498 @@
+use babysub::{BabysubError, Config, Preprocessor};
+
+fn sorted_clauses(preprocessor: &Preprocessor) -> Vec<Vec<i32>> {
+    let mut clauses: Vec<Vec<i32>> = preprocessor
+        .clauses()
+        .map(|clause| {
+            let mut clause = clause.to_vec();
+            clause.sort_unstable();
+            clause
+        })
+        .collect();
+    clauses.sort();
+    clauses
+}
+
+#[test]
+fn test_forward_subsumption_api() {
+    let mut preprocessor = Preprocessor::new(Config::default());
+    preprocessor.add_clause(&[1, 2]);
+    preprocessor.add_clause(&[1, 2, 3]);
+    preprocessor.add_clause(&[-1, 3]);
+    preprocessor.add_clause(&[2, 1, 1]);
+    preprocessor.forward_subsumption();
+
+    assert_eq!(preprocessor.formula().variables(), 3);
+    assert_eq!(sorted_clauses(&preprocessor), vec![vec![-1, 3], vec![1, 2]]);
+    assert_eq!(preprocessor.stats().parsed, 4);
+    assert_eq!(preprocessor.stats().subsumed, 2);
+}
+
+#[test]
+fn test_signature_filter() {
+    let mut preprocessor = Preprocessor::new(Config::default());
+    preprocessor.add_clause(&[1, 2]);
+    preprocessor.add_clause(&[1, 3, 4]);
+    preprocessor.add_clause(&[1, 2, 4]);
+    preprocessor.forward_subsumption();
+
+    assert_eq!(
+        sorted_clauses(&preprocessor),
+        vec![vec![1, 2], vec![1, 3, 4]]
+    );
+    assert_eq!(preprocessor.stats().checked, 2);
+    assert_eq!(preprocessor.stats().filtered, 1);
+}
+
+#[test]
+fn test_combined_subsumption_api() {
+    let config = Config {
+        strengthen: true,
+        ..Config::default()
+    };
+    let mut forward = Preprocessor::new(config.clone());
+    let mut combined = Preprocessor::new(config);
+    for preprocessor in [&mut forward, &mut combined] {
+        preprocessor.add_clause(&[1, 3]);
+        preprocessor.add_clause(&[3, -1]);
+    }
+    forward.forward_subsumption();
+    combined.combined_subsumption();
+
+    // only the second round finds that the strengthened clause subsumes
+    assert_eq!(sorted_clauses(&forward), vec![vec![1, 3], vec![3]]);
+    assert_eq!(sorted_clauses(&combined), vec![vec![3]]);
+    assert_eq!(combined.stats().rounds, 2);
+    assert_eq!(combined.stats().round_counts, vec![(0, 1), (1, 0)]);
+}
+
+#[test]
+fn test_incremental_simplify() {
+    let mut preprocessor = Preprocessor::new(Config::default());
+    preprocessor.add_clause(&[1, 2, 3]);
+    preprocessor.add_clause(&[1, -2]);
+    preprocessor.add_clause(&[4, 5]);
+    preprocessor.simplify().unwrap();
+    assert_eq!(preprocessor.formula().len(), 3);
+
+    // the first clause is subsumed backward, the second one forward
+    preprocessor.add_clause(&[2, 1]);
+    preprocessor.add_clause(&[4, 5, 6]);
+    preprocessor.simplify().unwrap();
+    assert_eq!(
+        sorted_clauses(&preprocessor),
+        vec![vec![-2, 1], vec![1, 2], vec![4, 5]]
+    );
+
+    assert!(preprocessor.remove_clause(&[-2, 1]).unwrap());
+    assert!(!preprocessor.remove_clause(&[1, 2, 3]).unwrap());
+    preprocessor.simplify().unwrap();
+    assert_eq!(sorted_clauses(&preprocessor), vec![vec![1, 2], vec![4, 5]]);
+}
+
+#[test]
+fn test_remove_used_clause() {
+    let config = Config {
+        strengthen: true,
+        ..Config::default()
+    };
+    let mut preprocessor = Preprocessor::new(config);
+    preprocessor.add_clause(&[1, 2]);
+    preprocessor.add_clause(&[1, 2, 3]);
+    preprocessor.add_clause(&[-3, 4]);
+    preprocessor.add_clause(&[3, 4, 5]);
+    preprocessor.simplify().unwrap();
+    assert_eq!(
+        sorted_clauses(&preprocessor),
+        vec![vec![-3, 4], vec![1, 2], vec![4, 5]]
+    );
+
+    // the subsumer and the antecedent of strengthening have to stay
+    for clause in [[2, 1], [4, -3]] {
+        assert!(matches!(
+            preprocessor.remove_clause(&clause),
+            Err(BabysubError::ClauseInUse(_))
+        ));
+    }
+    assert!(preprocessor.remove_clause(&[4, 5]).unwrap());
+    assert_eq!(sorted_clauses(&preprocessor), vec![vec![-3, 4], vec![1, 2]]);
+}
+
+#[test]
+fn test_remove_strengthened_clause() {
+    let config = Config {
+        strengthen: true,
+        ..Config::default()
+    };
+    let mut preprocessor = Preprocessor::new(config);
+    preprocessor.add_clause(&[1, 2]);
+    preprocessor.add_clause(&[-1, 2, 3]);
+    preprocessor.simplify().unwrap();
+    assert_eq!(sorted_clauses(&preprocessor), vec![vec![1, 2], vec![2, 3]]);
+
+    // the strengthened clause is only implied together with the original
+    assert!(matches!(
+        preprocessor.remove_clause(&[-1, 2, 3]),
+        Err(BabysubError::ClauseInUse(_))
+    ));
+    assert_eq!(sorted_clauses(&preprocessor), vec![vec![1, 2], vec![2, 3]]);
+}
+
+#[test]
+fn test_remove_antecedent_of_empty_clause() {
+    let strengthen = Config {
+        strengthen: true,
+        ..Config::default()
+    };
+    let propagate = Config {
+        propagate: true,
+        ..Config::default()
+    };
+    for config in [strengthen, propagate] {
+        let mut preprocessor = Preprocessor::new(config);
+        preprocessor.add_clause(&[2]);
+        preprocessor.add_clause(&[-2]);
+        preprocessor.simplify().unwrap();
+        assert_eq!(sorted_clauses(&preprocessor), vec![Vec::<i32>::new()]);
+
+        // without either unit the formula would be satisfiable again
+        for clause in [[2], [-2]] {
+            assert!(matches!(
+                preprocessor.remove_clause(&clause),
+                Err(BabysubError::ClauseInUse(_))
+            ));
+        }
+        assert_eq!(sorted_clauses(&preprocessor), vec![Vec::<i32>::new()]);
+    }
+}
+
+#[test]
+fn test_removed_clauses_not_counted() {
+    let output_path = std::env::temp_dir().join("babysub-removed-clauses.cnf");
+    let config = Config {
+        output_path: output_path.to_str().unwrap().to_string(),
+        ..Config::default()
+    };
+    let mut preprocessor = Preprocessor::new(config);
+    preprocessor.add_clause(&[1, 2]);
+    preprocessor.add_clause(&[-1, 3]);
+    preprocessor.add_clause(&[2, 3]);
+    preprocessor.simplify().unwrap();
+    assert!(preprocessor.remove_clause(&[3, -1]).unwrap());
+    assert_eq!(preprocessor.formula().len(), 2);
+
+    preprocessor.print().unwrap();
+    let printed = std::fs::read_to_string(&output_path).unwrap();
+    let _ = std::fs::remove_file(&output_path);
+    assert_eq!(printed, "p cnf 3 2\n1 2 0\n2 3 0\n");
+}
+
+#[test]
+fn test_freeze_api() {
+    let config = Config {
+        substitute: true,
+        eliminate: true,
+        ..Config::default()
+    };
+    let mut preprocessor = Preprocessor::new(config);
+    // 2 and 3 are equivalent, and every variable could be eliminated
+    preprocessor.add_clause(&[-2, 3]);
+    preprocessor.add_clause(&[2, -3]);
+    preprocessor.add_clause(&[1, 3]);
+    preprocessor.add_clause(&[-1, 4]);
+    for lit in [-1, 3, 4] {
+        preprocessor.freeze(lit);
+    }
+    preprocessor.simplify().unwrap();
+
+    // 3 is kept as representative of 2
+    assert_eq!(sorted_clauses(&preprocessor), vec![vec![-1, 4], vec![1, 3]]);
+    assert_eq!(preprocessor.stats().substituted, 1);
+    assert_eq!(preprocessor.stats().eliminated, 0);
+}
+
+#[test]
+fn test_backward_subsumption_api() {
+    let mut preprocessor = Preprocessor::new(Config::default());
+    preprocessor.add_clause(&[-1, 2]);
+    preprocessor.add_clause(&[1, 2]);
+    preprocessor.add_clause(&[2, -1]);
+    preprocessor.add_clause(&[1, -1]);
+    preprocessor.backward_subsumption();
+
+    assert_eq!(sorted_clauses(&preprocessor), vec![vec![-1, 2], vec![1, 2]]);
+    assert_eq!(preprocessor.stats().subsumed, 1);
+    assert_eq!(preprocessor.signature(), 13752085431583871717);
+}
+
+#[test]
+fn test_duplicate_removal() {
+    let mut preprocessor = Preprocessor::new(Config::default());
+    preprocessor.add_clause(&[1, 2]);
+    preprocessor.add_clause(&[2, 1]);
+    preprocessor.add_clause(&[-1, 3]);
+    preprocessor.add_clause(&[1, 2]);
+    preprocessor.simplify().unwrap();
+
+    assert_eq!(sorted_clauses(&preprocessor), vec![vec![-1, 3], vec![1, 2]]);
+    assert_eq!(preprocessor.stats().duplicated, 2);
+    assert_eq!(preprocessor.stats().subsumed, 0);
+}
+
+#[test]
+fn test_duplicate_removal_keeps_literal_order() {
+    let mut preprocessor = Preprocessor::new(Config::default());
+    preprocessor.add_clause(&[4, -2]);
+    preprocessor.add_clause(&[3, -1, 2]);
+    preprocessor.add_clause(&[2, 3, -1]);
+    preprocessor.simplify().unwrap();
+
+    let clauses: Vec<Vec<i32>> = preprocessor.clauses().map(<[i32]>::to_vec).collect();
+    assert_eq!(clauses, vec![vec![4, -2], vec![3, -1, 2]]);
+    assert_eq!(preprocessor.stats().duplicated, 1);
+}
+
+#[test]
+fn test_probing_propagates_once() {
+    let config = Config {
+        propagate: true,
+        probe: true,
+        ..Config::default()
+    };
+    let mut preprocessor = Preprocessor::new(config);
+    preprocessor.add_clause(&[5]);
+    preprocessor.add_clause(&[-5, 6]);
+    // probing 1 fails, which takes another round of propagation
+    preprocessor.add_clause(&[-1, 2]);
+    preprocessor.add_clause(&[-1, -2]);
+    preprocessor.add_clause(&[1, 3, 4]);
+    preprocessor.simplify().unwrap();
+    assert_eq!(preprocessor.stats().failed, 1);
+    assert_eq!(preprocessor.stats().units, 3);
+    assert_eq!(preprocessor.stats().propagated, 3);
+}
+
+#[test]
+fn test_probing_effort() {
+    let mut results = Vec::new();
+    for probe_effort in [0, 10] {
+        let config = Config {
+            probe: true,
+            probe_effort,
+            ..Config::default()
+        };
+        let mut preprocessor = Preprocessor::new(config);
+        // probing 1 fails
+        preprocessor.add_clause(&[-1, 2]);
+        preprocessor.add_clause(&[-1, -2]);
+        preprocessor.add_clause(&[1, 3, 4]);
+        preprocessor.simplify().unwrap();
+        results.push((
+            preprocessor.stats().failed,
+            preprocessor.stats().probe_ticks,
+        ));
+    }
+    assert_eq!(results[0], (0, 0));
+    assert_eq!(results[1].0, 1);
+    assert!(results[1].1 > 0);
+}
+
+#[test]
+fn test_backward_strengthening_api() {
+    let config = Config {
+        strengthen: true,
+        ..Config::default()
+    };
+    let mut preprocessor = Preprocessor::new(config);
+    preprocessor.add_clause(&[1, 2, 3]);
+    preprocessor.add_clause(&[-1, 2]);
+    preprocessor.backward_subsumption();
+
+    assert_eq!(sorted_clauses(&preprocessor), vec![vec![-1, 2], vec![2, 3]]);
+    assert_eq!(preprocessor.stats().strengthened, 1);
+}
+
+#[test]
+fn test_strengthening_to_empty_clause() {
+    let config = Config {
+        strengthen: true,
+        ..Config::default()
+    };
+    let mut preprocessor = Preprocessor::new(config);
+    preprocessor.add_clause(&[1]);
+    preprocessor.add_clause(&[-1]);
+    preprocessor.add_clause(&[2, 3]);
+    preprocessor.forward_subsumption();
+
+    assert_eq!(sorted_clauses(&preprocessor), vec![Vec::<i32>::new()]);
+}
+
+#[test]
+fn test_elimination_and_extension_api() {
+    let config = Config {
+        eliminate: true,
+        ..Config::default()
+    };
+    let original = [
+        vec![1, 2],
+        vec![-1, 3],
+        vec![-2, 3],
+        vec![-3, 4],
+        vec![-4, -1],
+    ];
+    let mut preprocessor = Preprocessor::new(config);
+    for clause in &original {
+        preprocessor.add_clause(clause);
+    }
+    preprocessor.simplify().unwrap();
+    assert!(preprocessor.formula().is_empty());
+
+    let model = preprocessor.extend(&[]);
+    assert_eq!(model.len(), 4);
+    for clause in &original {
+        assert!(clause.iter().any(|lit| model.contains(lit)));
+    }
+}
+
+#[test]
+fn test_blocked_clause_elimination_api() {
+    let config = Config {
+        block: true,
+        ..Config::default()
+    };
+    let original = [vec![1, 2], vec![-1, -2, 3], vec![-1, 4], vec![2, -3, -4]];
+    let mut preprocessor = Preprocessor::new(config);
+    for clause in &original {
+        preprocessor.add_clause(clause);
+    }
+    preprocessor.simplify().unwrap();
+    assert!(preprocessor.formula().is_empty());
+    assert_eq!(preprocessor.stats().blocked, 4);
+
+    let model = preprocessor.extend(&[]);
+    for clause in &original {
+        assert!(clause.iter().any(|lit| model.contains(lit)));
+    }
+}
+
+#[test]
+fn test_substitution_and_extension_api() {
+    let config = Config {
+        substitute: true,
+        ..Config::default()
+    };
+    let original = [vec![-1, -2], vec![2, 1], vec![2, 3], vec![-1, -3]];
+    let mut preprocessor = Preprocessor::new(config);
+    for clause in &original {
+        preprocessor.add_clause(clause);
+    }
+    preprocessor.simplify().unwrap();
+    assert_eq!(
+        sorted_clauses(&preprocessor),
+        vec![vec![-3, -1], vec![-1, 3]]
+    );
+    assert_eq!(preprocessor.stats().substituted, 1);
+
+    let model = preprocessor.extend(&[-1, 3]);
+    assert_eq!(model, vec![-1, 2, 3]);
+    for clause in &original {
+        assert!(clause.iter().any(|lit| model.contains(lit)));
+    }
+}
+
+#[test]
+fn test_unit_propagation_conflict() {
+    let config = Config {
+        propagate: true,
+        ..Config::default()
+    };
+    let mut preprocessor = Preprocessor::new(config);
+    preprocessor.add_clause(&[1]);
+    preprocessor.add_clause(&[-1, 2]);
+    preprocessor.add_clause(&[-2, 3]);
+    preprocessor.add_clause(&[-3, -1]);
+    preprocessor.simplify().unwrap();
+
+    assert_eq!(sorted_clauses(&preprocessor), vec![Vec::<i32>::new()]);
+    assert_eq!(preprocessor.stats().units, 3);
+}
+
+#[test]
+fn test_compaction_api() {
+    let mut preprocessor = Preprocessor::new(Config::default());
+    preprocessor.add_clause(&[2, 4]);
+    preprocessor.add_clause(&[2, 4, 1]);
+    preprocessor.add_clause(&[-4, 6]);
+    preprocessor.freeze(5);
+    preprocessor.simplify().unwrap();
+
+    let mapping = preprocessor.mapping();
+    assert_eq!(mapping.variables(), 6);
+    assert_eq!(mapping.len(), 4);
+    assert_eq!(mapping.original(3), Some(5));
+    assert_eq!(mapping.original(5), None);
+    assert_eq!(mapping.translate(&[1, -2, 3, -4, 5]), vec![2, -4, 5, -6]);
+}
+
+#[test]
+fn test_parallel_backward_subsumption() {
+    let mut results = Vec::new();
+    for threads in [1, 2, 3, 8] {
+        let config = Config {
+            threads,
+            ..Config::default()
+        };
+        let mut preprocessor = Preprocessor::new(config);
+        // a fixed pseudo random formula over few variables subsumes a lot
+        let mut seed: u32 = 1;
+        for _ in 0..500 {
+            let mut clause = Vec::new();
+            for var in 1..=12 {
+                seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+                match seed >> 28 {
+                    0..=2 => clause.push(var),
+                    3..=5 => clause.push(-var),
+                    _ => {}
+                }
+            }
+            if !clause.is_empty() {
+                preprocessor.add_clause(&clause);
+            }
+        }
+        preprocessor.backward_subsumption();
+        results.push((
+            preprocessor
+                .clauses()
+                .map(<[i32]>::to_vec)
+                .collect::<Vec<_>>(),
+            preprocessor.signature(),
+            preprocessor.stats().subsumed,
+        ));
+    }
+    assert!(results[0].2 > 0);
+    assert!(results.iter().all(|result| *result == results[0]));
+}
+
+#[test]
+fn test_mapped_and_streamed_parsing() {
+    let mut results = Vec::new();
+    for input in ["tokens1.cnf", "gzipped1.cnf"] {
+        for mmap in [false, true] {
+            let config = Config {
+                input_path: format!("tests/test_cases/{}", input),
+                mmap,
+                strict: true,
+                ..Config::default()
+            };
+            let mut preprocessor = Preprocessor::new(config);
+            preprocessor.parse().unwrap();
+            results.push((
+                sorted_clauses(&preprocessor),
+                preprocessor.formula().variables(),
+            ));
+        }
+    }
+    assert_eq!(results[0].0.len(), 5);
+    assert_eq!(results[0], results[1]);
+    assert_eq!(results[2], results[3]);
+}